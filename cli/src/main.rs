@@ -1,16 +1,78 @@
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-use mancala::{ayoayo::Ayoayo, GameState, MancalaError};
+use mancala::ai::{self, SearchConfig};
+use mancala::tablebase::Tablebase;
+use mancala::{ayoayo::Ayoayo, game::Game, record::Record, GameState, MancalaError};
+use std::cmp::Ordering;
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::process;
 
 enum Command {
     Quit,
+    Undo,
+    Redo,
+    Position,
+    Record,
+    Computer,
+    Endgame,
     Unknown(String),
     Play(usize),
 }
 
+const USAGE: &str = "usage: cli [--table <file>] | cli tablebase <seeds> <file>";
+
+// What to do once the arguments are dealt with.
+enum Start {
+    Play(Option<Tablebase>),
+    Done,
+}
+
+// `cli tablebase <seeds> <file>` writes an endgame table, `cli --table <file>`
+// plays with one to look things up in, and `cli` plays without one.
+fn start(args: &[String]) -> Result<Start, String> {
+    match args {
+        [] => Ok(Start::Play(None)),
+        [flag, path] if flag == "--table" => {
+            let file =
+                File::open(path).map_err(|err| format!("Couldn't open {}: {}", path, err))?;
+            let table = Tablebase::read(BufReader::new(file))
+                .map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            Ok(Start::Play(Some(table)))
+        }
+        [command, seeds, path] if command == "tablebase" => {
+            let seeds = seeds
+                .parse()
+                .map_err(|_| format!("{} isn't a number of seeds", seeds))?;
+            let table = Tablebase::generate(Ayoayo::new(), seeds)
+                .map_err(|err| format!("Couldn't build a table for {} seeds: {}", seeds, err))?;
+            let mut out = File::create(path)
+                .map(BufWriter::new)
+                .map_err(|err| format!("Couldn't create {}: {}", path, err))?;
+            table
+                .write(&mut out)
+                .and_then(|()| out.flush())
+                .map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+            Ok(Start::Done)
+        }
+        _ => Err(String::from(USAGE)),
+    }
+}
+
 fn main() {
-    let mut game = Ayoayo::new();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let tablebase = match start(&args) {
+        Ok(Start::Play(tablebase)) => tablebase,
+        Ok(Start::Done) => return,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut game = Game::new(Ayoayo::new());
     println!("{}", game);
     let mut rl = Editor::<()>::new();
     loop {
@@ -23,7 +85,16 @@ fn main() {
                 println!("{} Won!", player);
                 break;
             }
-            GameState::InProgress(player) => rl.readline(format!("{}'s Turn: ", player).as_ref()),
+            GameState::InProgress(player) => {
+                let cups = game
+                    .legal_moves()
+                    .iter()
+                    .map(|cup| (cup + 1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                rl.readline(format!("{}'s Turn ({}): ", player, cups).as_ref())
+            }
+            GameState::Simultaneous => rl.readline("Both players' Turn: "),
         };
         match readline.map(string_to_command) {
             Ok(Command::Play(size)) if size > 0 => match game.play(size - 1) {
@@ -33,9 +104,80 @@ fn main() {
                 Err(MancalaError::MustFeedError) => println!("You must feed your oponent seeds"),
                 Err(MancalaError::NoSeedsToSow) => println!("The cup you chose has no seeds"),
                 Err(MancalaError::NoSuchCup) => println!("The cup you chose doesn't exist"),
+                Err(MancalaError::GrandSlam) => {
+                    println!("You can't capture all of your opponent's seeds")
+                }
+                Err(MancalaError::MustCapture) => println!("You have to make a capture"),
+                Err(MancalaError::IllegalMove) => println!("That move isn't allowed"),
+                Err(MancalaError::InvalidBoard) => {
+                    println!("The board is in a state it can't be in")
+                }
+                Err(MancalaError::InvalidNotation) => {
+                    println!("That isn't a position or game record")
+                }
             },
             Ok(Command::Play(_)) => println!("The cup you chose doesn't exist"),
             Ok(Command::Quit) => break,
+            Ok(Command::Undo) => {
+                if game.undo() {
+                    println!("{}", game);
+                } else {
+                    println!("There's nothing to undo");
+                }
+            }
+            Ok(Command::Redo) => {
+                if game.redo() {
+                    println!("{}", game);
+                } else {
+                    println!("There's nothing to redo");
+                }
+            }
+            Ok(Command::Position) => match game.position() {
+                Some(position) => println!("{}", position),
+                None => println!("Nobody is to move"),
+            },
+            Ok(Command::Record) => match Record::from_game(&game) {
+                Ok(record) => print!("{}", record),
+                Err(_) => println!("This game can't be written as a record"),
+            },
+            Ok(Command::Computer) => {
+                let result = match game.position() {
+                    Some(position) => {
+                        ai::search(&Ayoayo::new(), &position, &SearchConfig::default())
+                    }
+                    None => Ok(None),
+                };
+                match result {
+                    Ok(Some(result)) => {
+                        println!("The computer plays {}", result.cup + 1);
+                        if game.play(result.cup).is_ok() {
+                            println!("{}", game);
+                        }
+                    }
+                    Ok(None) => println!("There's nothing for the computer to play"),
+                    Err(_) => println!("The computer can't read this board"),
+                }
+            }
+            Ok(Command::Endgame) => {
+                let probe = tablebase
+                    .as_ref()
+                    .and_then(|table| game.position().and_then(|position| table.probe(&position)));
+                match probe {
+                    Some(probe) => match probe.best {
+                        Some(cup) => match probe.margin.cmp(&0) {
+                            Ordering::Greater => {
+                                println!("Play {} to win by {} seeds", cup + 1, probe.margin)
+                            }
+                            Ordering::Less => {
+                                println!("Play {} to lose by {} seeds", cup + 1, -probe.margin)
+                            }
+                            Ordering::Equal => println!("Play {} to draw", cup + 1),
+                        },
+                        None => println!("The game is over"),
+                    },
+                    None => println!("The endgame table doesn't have this position"),
+                }
+            }
             Ok(Command::Unknown(command)) => {
                 println!("Command not found: {}", command);
                 break;
@@ -53,6 +195,12 @@ fn main() {
 fn string_to_command(string: String) -> Command {
     match string.trim() {
         "quit" => Command::Quit,
+        "undo" => Command::Undo,
+        "redo" => Command::Redo,
+        "position" => Command::Position,
+        "record" => Command::Record,
+        "computer" => Command::Computer,
+        "endgame" => Command::Endgame,
         c if c.parse::<usize>().is_ok() => {
             Command::Play(c.parse::<usize>().expect("already tested"))
         }
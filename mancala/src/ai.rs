@@ -0,0 +1,355 @@
+//! A computer player for Ayoayo: iterative-deepening negamax with
+//! alpha-beta pruning, played out on a `Position` with
+//! `Ayoayo::play_move` so it follows the same rules as `Game::play`.
+//!
+//! Scores are from the point of view of the side to move: what an
+//! `Evaluator` makes of the position, the bank difference unless another is
+//! given, or `WIN` less the number of moves it takes once the game can be won.
+
+use crate::ayoayo::Ayoayo;
+use crate::eval::{BankDifference, Evaluator};
+use crate::position::Position;
+use crate::{GameState, Result};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The score of a won game, less one for every move it takes to win.
+pub const WIN: i32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchConfig {
+    /// How many moves ahead to look at most.
+    pub depth: usize,
+    /// When set, stops at the last depth finished before the time runs out.
+    /// Clocks aren't available everywhere, web builds included, so leave it
+    /// unset there.
+    pub time: Option<Duration>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            depth: 8,
+            time: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub cup: usize,
+    pub score: i32,
+    /// The deepest search that finished.
+    pub depth: usize,
+    /// The moves both sides are expected to play, starting with `cup`.
+    pub principal_variation: Vec<usize>,
+    pub nodes: u64,
+}
+
+/// Picks a move for the side to move in `position`, or None when there's
+/// nothing to play. Fails with `InvalidBoard` if `position` doesn't have
+/// the cups `rules` sets up, or with a move's error if no legal move can
+/// be played.
+pub fn search(
+    rules: &Ayoayo,
+    position: &Position,
+    config: &SearchConfig,
+) -> Result<Option<SearchResult>> {
+    search_with(rules, position, config, &BankDifference)
+}
+
+/// Like `search`, scoring the positions at the end of each line with `evaluator`.
+pub fn search_with(
+    rules: &Ayoayo,
+    position: &Position,
+    config: &SearchConfig,
+    evaluator: &dyn Evaluator,
+) -> Result<Option<SearchResult>> {
+    rules.check_position(position)?;
+    // A legal cup can still go round forever, and if they all do there's
+    // nothing the search could pick.
+    let mut played = rules
+        .legal_cups(position)
+        .map(|cup| rules.play_move(position, cup))
+        .collect::<Vec<_>>();
+    if played.iter().all(|after| after.is_err()) {
+        if let Some(Err(err)) = played.pop() {
+            return Err(err);
+        }
+    }
+    let mut search = Search {
+        rules,
+        evaluator,
+        deadline: config.time.map(|time| Instant::now() + time),
+        nodes: 0,
+        best_moves: HashMap::new(),
+        pv: Vec::new(),
+    };
+    let mut result = None;
+    for depth in 1..=config.depth.max(1) {
+        search.pv = vec![Vec::new(); depth + 1];
+        let score = match search.negamax(position, depth, -WIN - 1, WIN + 1, 0) {
+            Some(score) => score,
+            None => break,
+        };
+        let principal_variation = search.pv[0].clone();
+        let cup = match principal_variation.first() {
+            Some(cup) => *cup,
+            None => break,
+        };
+        result = Some(SearchResult {
+            cup,
+            score,
+            depth,
+            principal_variation,
+            nodes: search.nodes,
+        });
+        if score.abs() > WIN - depth as i32 {
+            // The game is decided within this depth.
+            break;
+        }
+    }
+    Ok(result)
+}
+
+struct Search<'a> {
+    rules: &'a Ayoayo,
+    evaluator: &'a dyn Evaluator,
+    deadline: Option<Instant>,
+    nodes: u64,
+    // The best move found for each position searched, tried first next time.
+    best_moves: HashMap<u64, usize>,
+    // The principal variation from each ply.
+    pv: Vec<Vec<usize>>,
+}
+
+impl<'a> Search<'a> {
+    fn evaluate(&self, position: &Position) -> i32 {
+        self.evaluator
+            .evaluate(self.rules, position, position.to_move)
+    }
+
+    // Cups to try, most promising first: the best move from an earlier
+    // search of this position, then the ones that bank the most straight away.
+    fn ordered_moves(&self, position: &Position) -> Vec<usize> {
+        let remembered = self.best_moves.get(&position.zobrist()).copied();
+        let mut moves: Vec<(i32, usize)> = self
+            .rules
+            .legal_cups(position)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|cup| {
+                let score = if Some(cup) == remembered {
+                    i32::MAX
+                } else {
+                    match self.rules.play_move(position, cup) {
+                        Ok(after) => -self.evaluate(&after),
+                        Err(_) => i32::MIN,
+                    }
+                };
+                (score, cup)
+            })
+            .collect();
+        moves.sort_by_key(|(score, _)| Reverse(*score));
+        moves.into_iter().map(|(_, cup)| cup).collect()
+    }
+
+    // The score of `position` searched `depth` moves deep, or None if the
+    // time ran out.
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: usize,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if let Some(deadline) = self.deadline {
+            if self.nodes.is_multiple_of(1024) && Instant::now() >= deadline {
+                return None;
+            }
+        }
+        self.pv[ply].clear();
+        match self.rules.game_state(position) {
+            GameState::Won(player) if player == position.to_move => return Some(WIN - ply as i32),
+            GameState::Won(_) => return Some(-(WIN - ply as i32)),
+            GameState::Draw => return Some(0),
+            _ if depth == 0 => return Some(self.evaluate(position)),
+            _ => (),
+        }
+
+        let mut best = None;
+        for cup in self.ordered_moves(position) {
+            let after = match self.rules.play_move(position, cup) {
+                Ok(after) => after,
+                Err(_) => continue,
+            };
+            let score = -self.negamax(&after, depth - 1, -beta, -alpha, ply + 1)?;
+            if best.is_none_or(|best| score > best) {
+                best = Some(score);
+                let mut line = vec![cup];
+                line.extend_from_slice(&self.pv[ply + 1]);
+                self.pv[ply] = line;
+                self.best_moves.insert(position.zobrist(), cup);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        // Every move went round forever, so there's nothing to play.
+        Some(best.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::AyoayoConfig;
+    use crate::eval::{Mobility, Vulnerable, Weighted};
+    use crate::game::Game;
+    use crate::MancalaError;
+
+    // Plain negamax without pruning, to check the search against.
+    fn minimax(rules: &Ayoayo, position: &Position, depth: usize, ply: usize) -> i32 {
+        match rules.game_state(position) {
+            GameState::Won(player) if player == position.to_move => return WIN - ply as i32,
+            GameState::Won(_) => return -(WIN - ply as i32),
+            GameState::Draw => return 0,
+            _ if depth == 0 => {
+                let player = position.to_move;
+                return position.bank(player) as i32 - position.bank(player.next_player()) as i32;
+            }
+            _ => (),
+        }
+        let mut best = None;
+        for cup in rules.legal_cups(position).collect::<Vec<_>>() {
+            if let Ok(after) = rules.play_move(position, cup) {
+                let score = -minimax(rules, &after, depth - 1, ply + 1);
+                best = Some(best.map_or(score, |best: i32| best.max(score)));
+            }
+        }
+        best.unwrap_or(0)
+    }
+
+    #[test]
+    fn matches_minimax() -> Result<()> {
+        let rules = Ayoayo::new();
+        let mut game = Game::new(rules);
+        for cup in [3, 0, 0, 4, 2, 1].iter() {
+            game.play(*cup)?;
+            let position = game.position().unwrap();
+            for depth in 1..=4 {
+                let config = SearchConfig { depth, time: None };
+                let result = search(&rules, &position, &config)?.unwrap();
+                assert_eq!(minimax(&rules, &position, depth, 0), result.score);
+                assert_eq!(depth, result.depth);
+                assert_eq!(result.cup, result.principal_variation[0]);
+
+                // The principal variation can be played out.
+                let mut replay = game.clone();
+                for cup in result.principal_variation.iter() {
+                    replay.play(*cup)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn takes_a_win() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })?;
+        // Sowing the first cup captures all of Player 2's seeds and ends the game.
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        let result = search(&rules, &position, &SearchConfig::default())?.unwrap();
+        assert_eq!(0, result.cup);
+        assert_eq!(WIN - 1, result.score);
+        assert_eq!(vec![0], result.principal_variation);
+        Ok(())
+    }
+
+    #[test]
+    fn must_feed() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })?;
+        // Only the last cup reaches Player 2, so it's the only legal move.
+        let position: Position = "1,0,1/0,0,0 8/10 0/0 1".parse()?;
+        let game = position.to_game(rules)?;
+        let result = search(&rules, &position, &SearchConfig::default())?.unwrap();
+        assert_eq!(game.legal_moves(), vec![result.cup]);
+        Ok(())
+    }
+
+    #[test]
+    fn other_evaluators() -> Result<()> {
+        let rules = Ayoayo::new();
+        let mut game = Game::new(rules);
+        game.play(3)?;
+        let position = game.position().unwrap();
+        let config = SearchConfig {
+            depth: 3,
+            time: None,
+        };
+        let evaluator = Weighted::new()
+            .with(4, BankDifference)
+            .with(1, Mobility)
+            .with(1, Vulnerable);
+        let result = search_with(&rules, &position, &config, &evaluator)?.unwrap();
+        assert!(rules.check_move(&position, result.cup).is_ok());
+
+        // Any function of the position will do.
+        let result = search_with(&rules, &position, &config, &|_: &Ayoayo, _: &Position, _| 0);
+        assert_eq!(0, result?.unwrap().score);
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_board() -> Result<()> {
+        let position = Game::new(Ayoayo::new()).position().unwrap();
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })?;
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            search(&rules, &position, &SearchConfig::default())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn every_move_relays_forever() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 1,
+            ..AyoayoConfig::default()
+        })?;
+        let position: Position = "2/3 0/0 0/0 1".parse()?;
+        assert_eq!(vec![0], rules.legal_cups(&position).collect::<Vec<_>>());
+        assert_eq!(
+            Err(MancalaError::IllegalMove),
+            search(&rules, &position, &SearchConfig::default())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn time_budget() -> Result<()> {
+        let rules = Ayoayo::new();
+        let position = Game::new(rules).position().unwrap();
+        let config = SearchConfig {
+            depth: 100,
+            time: Some(Duration::from_millis(50)),
+        };
+        let result = search(&rules, &position, &config)?.unwrap();
+        assert!(result.depth < 100);
+        assert!(rules.check_move(&position, result.cup).is_ok());
+        Ok(())
+    }
+}
@@ -1,126 +1,327 @@
-use crate::board::{Cup, CupPos, MancalaBoard};
+use crate::board::{skip_origin, Cup, CupPos, MancalaBoard, Pit, MAX_CUPS};
+use crate::game::{end_game, must_feed, winner, Ruleset, MAX_RELAYS};
+use crate::position::Position;
 use crate::{GameState, MancalaError, Player, Result};
-use compare::{natural, Compare};
-use std::cmp::Ordering;
-use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-const BOARD_SIZE: usize = 12;
+const PITS: usize = 6;
 const STARTING_COUNT: usize = 4;
 
-#[derive(Clone, PartialEq)]
-pub struct Ayoayo {
-    pub(crate) board: MancalaBoard,
-    pub state: GameState,
+/// Who takes the seeds still on the board when the game ends. The game ends
+/// once a move leaves the opponent without seeds, so they're all on the
+/// side of the player who just moved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Leftovers {
+    /// The player who made the last move.
+    Mover,
+    /// The player who was left without seeds.
+    Opponent,
+    /// Nobody, they stay on the board and don't count.
+    Nobody,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AyoayoConfig {
+    /// Cups on each player's side.
+    pub pits: usize,
+    /// Seeds in every cup at the start.
+    pub seeds: usize,
+    /// Whether a player has to give seeds to an opponent who has none.
+    pub must_feed: bool,
+    pub leftovers: Leftovers,
 }
 
-impl Default for Ayoayo {
+impl Default for AyoayoConfig {
     fn default() -> Self {
-        Self::new()
+        AyoayoConfig {
+            pits: PITS,
+            seeds: STARTING_COUNT,
+            must_feed: true,
+            leftovers: Leftovers::Mover,
+        }
     }
 }
 
-impl Ayoayo {
-    pub fn get_cups_for_player(&self, player: Player) -> Vec<Cup> {
-        self.board
-            .cups
-            .iter()
-            .filter(|cup| cup.owner == player)
-            .cloned()
-            .collect::<Vec<_>>()
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "AyoayoData", into = "AyoayoData")
+)]
+pub struct Ayoayo {
+    config: AyoayoConfig,
+}
+
+// What the rules are serialized as, and deserialize from before the config
+// is checked by `with_config`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct AyoayoData {
+    config: AyoayoConfig,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<AyoayoData> for Ayoayo {
+    type Error = MancalaError;
+
+    fn try_from(data: AyoayoData) -> Result<Ayoayo> {
+        Ayoayo::with_config(data.config)
     }
+}
 
-    pub fn get_bank(&self, player: Player) -> usize {
-        self.board.bank.get(player)
+#[cfg(feature = "serde")]
+impl From<Ayoayo> for AyoayoData {
+    fn from(rules: Ayoayo) -> AyoayoData {
+        AyoayoData {
+            config: rules.config,
+        }
     }
 }
 
+/// What `Ayoayo::unmake_move` needs to take a move back. Relays and the
+/// sweep at the end of a game can reach every cup, so it keeps the seeds as
+/// they were instead of working the sowing out backwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Undo {
+    seeds: [u16; MAX_CUPS],
+    banks: [usize; 2],
+}
+
 impl Ayoayo {
     pub fn new() -> Ayoayo {
-        let board: Vec<Cup> = [Player::Player1, Player::Player2]
-            .iter()
-            .flat_map(|player| {
-                (0..(BOARD_SIZE / 2)).map(move |i| Cup {
-                    owner: *player,
-                    seeds: STARTING_COUNT,
-                    pos: i,
-                })
-            })
-            .collect();
+        Ayoayo::default()
+    }
 
-        Ayoayo {
-            board: MancalaBoard::new(board),
-            state: GameState::InProgress(Player::Player1),
+    /// Rules for `config`. Fails with `InvalidBoard` unless there's at least
+    /// one cup and one seed, the cups fit on a board and every seed would
+    /// fit in one cup of a `Position`.
+    pub fn with_config(config: AyoayoConfig) -> Result<Ayoayo> {
+        let seeds = (2 * config.pits).checked_mul(config.seeds);
+        if config.pits == 0
+            || 2 * config.pits > MAX_CUPS
+            || config.seeds == 0
+            || seeds.is_none_or(|seeds| seeds > u16::MAX as usize)
+        {
+            return Err(MancalaError::InvalidBoard);
         }
+        Ok(Ayoayo { config })
     }
 
-    fn sow_filter(check_cup: &CupPos, player: Player, start_cup: usize) -> bool {
-        let cup = check_cup;
-        !(cup.owner == player && cup.pos == start_cup)
+    pub fn config(&self) -> &AyoayoConfig {
+        &self.config
     }
 
-    fn sow(&mut self, player: Player, cup: usize) -> Result<()> {
-        self.board.pickup(
-            CupPos {
-                owner: player,
-                pos: cup,
-            },
-            player,
-        );
-        let mut last = self.board.sow(
+    // Who takes the seeds left when `player`'s move ends the game.
+    fn collector(&self, player: Player) -> Option<Player> {
+        match self.config.leftovers {
+            Leftovers::Mover => Some(player),
+            Leftovers::Opponent => Some(player.next_player()),
+            Leftovers::Nobody => None,
+        }
+    }
+
+    // Where `player`'s cup `pos` is in a position's seeds.
+    fn index(&self, player: Player, pos: usize) -> usize {
+        match player {
+            Player::Player1 => pos,
+            Player::Player2 => self.config.pits + pos,
+        }
+    }
+
+    /// Fails with `InvalidBoard` unless `position` has the cups these rules
+    /// set up.
+    pub(crate) fn check_position(&self, position: &Position) -> Result<()> {
+        if position.seeds().len() == 2 * self.config.pits {
+            Ok(())
+        } else {
+            Err(MancalaError::InvalidBoard)
+        }
+    }
+
+    /// The seeds in `player`'s cups in `position`.
+    pub fn side<'a>(&self, position: &'a Position, player: Player) -> Result<&'a [u16]> {
+        self.check_position(position)?;
+        Ok(self.row(position, player))
+    }
+
+    /// Like `MancalaBoard::starving`, for a position.
+    pub fn starving(&self, position: &Position, player: Player) -> Result<bool> {
+        self.check_position(position)?;
+        Ok(self.empty(position, player))
+    }
+
+    /// Who has won a position, or whose turn it is. The game is over once
+    /// the side to move has no seeds.
+    pub fn outcome(&self, position: &Position) -> Result<GameState> {
+        self.check_position(position)?;
+        Ok(self.game_state(position))
+    }
+
+    // `side`, `starving` and `outcome` for positions that have been checked.
+    pub(crate) fn row<'a>(&self, position: &'a Position, player: Player) -> &'a [u16] {
+        let first = self.index(player, 0);
+        &position.seeds()[first..first + self.config.pits]
+    }
+
+    fn empty(&self, position: &Position, player: Player) -> bool {
+        self.row(position, player).iter().all(|seeds| *seeds == 0)
+    }
+
+    pub(crate) fn game_state(&self, position: &Position) -> GameState {
+        let player = position.to_move;
+        if !self.empty(position, player) {
+            return GameState::InProgress(player);
+        }
+        winner(
             player,
-            CupPos {
-                owner: player,
-                pos: cup,
-            },
-            Ayoayo::sow_filter,
-        )?;
-        while last.seeds > 1 {
-            let cup_pos = CupPos::from(&last);
-            self.board.pickup(cup_pos, player);
-            last = self.board.sow(player, cup_pos, Ayoayo::sow_filter)?;
+            position.bank(player),
+            position.bank(player.next_player()),
+        )
+    }
+
+    /// Checks a move from `position` by the same rules as `validate`,
+    /// without building a board.
+    pub fn check_move(&self, position: &Position, cup: usize) -> Result<()> {
+        let player = position.to_move;
+        self.check_position(position)?;
+        if self.empty(position, player) {
+            return Err(MancalaError::IllegalMove);
+        } else if cup >= self.config.pits {
+            return Err(MancalaError::NoSuchCup);
+        } else if self.row(position, player)[cup] == 0 {
+            return Err(MancalaError::NoSeedsToSow);
         }
-        if last.owner == player {
-            self.board.pickup(
-                CupPos {
-                    owner: player.next_player(),
-                    pos: last.pos,
-                },
-                player,
-            );
-            self.board.bank(player);
+
+        let opponent = player.next_player();
+        let feeds = |cup: usize| {
+            let mut after = *position;
+            self.sow_position(&mut after, player, cup).is_ok() && !self.empty(&after, opponent)
         };
+        if self.config.must_feed && self.empty(position, opponent) && !feeds(cup) {
+            let could_feed = (0..self.config.pits)
+                .filter(|other| *other != cup && self.row(position, player)[*other] > 0)
+                .any(feeds);
+            if could_feed {
+                return Err(MancalaError::MustFeedError);
+            }
+        }
         Ok(())
     }
 
-    fn win_state(&mut self, player: Player) {
-        for cup in self.board.cups.clone().iter() {
-            let cup_pos = CupPos::from(cup);
-            self.board.pickup(cup_pos, player);
-            self.board.bank(player);
+    /// The cups the side to move can play from `position`.
+    pub fn legal_cups<'a>(&'a self, position: &'a Position) -> impl Iterator<Item = usize> + 'a {
+        (0..self.config.pits).filter(move |cup| self.check_move(position, *cup).is_ok())
+    }
+
+    /// Plays `cup` for the side to move, changing `position` in place. Unlike
+    /// `Game::play` nothing is logged or allocated, and the returned `Undo`
+    /// takes the move back with `unmake_move`. A move that fails leaves
+    /// `position` as it was.
+    pub fn make_move(&self, position: &mut Position, cup: usize) -> Result<Undo> {
+        self.check_move(position, cup)?;
+        let undo = Undo {
+            seeds: position.seeds,
+            banks: position.banks,
+        };
+        let player = position.to_move;
+        if let Err(err) = self.sow_position(position, player, cup) {
+            position.seeds = undo.seeds;
+            position.banks = undo.banks;
+            return Err(err);
         }
-        self.state = match natural().compare(
-            &self.board.bank.get(player),
-            &self.board.bank.get(player.next_player()),
-        ) {
-            Ordering::Less => GameState::Won(player.next_player()),
-            Ordering::Greater => GameState::Won(player),
-            Ordering::Equal => GameState::Draw,
+
+        let opponent = player.next_player();
+        if self.empty(position, opponent) {
+            if let Some(collector) = self.collector(player) {
+                let left: usize = position.seeds().iter().map(|seeds| *seeds as usize).sum();
+                position.seeds = [0; MAX_CUPS];
+                *position.bank_mut(collector) += left;
+            }
         }
+        position.to_move = opponent;
+        Ok(undo)
     }
 
-    // Game over Check (No valid moves)
-    // Feeding check (Must give other player seeds if other player has no seeds _at start of play_)
-    pub fn play(&mut self, cup: usize) -> Result<()> {
-        let player = match self.state {
-            GameState::InProgress(p) => p,
-            _ => return Ok(()),
-        };
+    /// Puts `position` back exactly as it was before the move `undo` came from.
+    pub fn unmake_move(&self, position: &mut Position, undo: Undo) {
+        position.seeds = undo.seeds;
+        position.banks = undo.banks;
+        position.to_move = position.to_move.next_player();
+    }
+
+    /// The position after the side to move plays `cup`, leaving `position`
+    /// as it is. See `make_move`.
+    pub fn play_move(&self, position: &Position, cup: usize) -> Result<Position> {
+        let mut after = *position;
+        self.make_move(&mut after, cup)?;
+        Ok(after)
+    }
 
-        if cup >= (BOARD_SIZE / 2) {
+    // Sows `cup`, relays and captures the way `sow` and `capture` do on a
+    // board, a lap at a time.
+    fn sow_position(&self, position: &mut Position, player: Player, cup: usize) -> Result<()> {
+        let ring = 2 * self.config.pits;
+        let mut origin = self.index(player, cup);
+        for _ in 0..=MAX_RELAYS {
+            let hand = std::mem::take(&mut position.seeds[origin]) as usize;
+            // The origin is skipped, so every other cup gets a seed a lap.
+            let open = ring - 1;
+            let (laps, rest) = (hand / open, hand % open);
+            if laps > 0 {
+                for (index, seeds) in position.seeds[..ring].iter_mut().enumerate() {
+                    if index != origin {
+                        *seeds += laps as u16;
+                    }
+                }
+            }
+            for step in 1..=rest {
+                position.seeds[(origin + step) % ring] += 1;
+            }
+            let last = (origin + if rest > 0 { rest } else { open }) % ring;
+            if position.seeds[last] > 1 {
+                origin = last;
+                continue;
+            }
+
+            let own = self.index(player, 0);
+            if (own..own + self.config.pits).contains(&last) {
+                let opposite = self.index(player.next_player(), last - own);
+                let captured = std::mem::take(&mut position.seeds[opposite]) as usize;
+                *position.bank_mut(player) += captured;
+            }
+            return Ok(());
+        }
+        Err(MancalaError::IllegalMove)
+    }
+}
+
+impl Ruleset for Ayoayo {
+    type Move = usize;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..self.config.pits).map(move |i| Cup {
+                    owner: *player,
+                    seeds: self.config.seeds,
+                    pos: i,
+                })
+            })
+            .collect();
+
+        MancalaBoard::new(board).expect("`with_config` checked the board fits")
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, cup: usize) -> Result<()> {
+        if cup >= self.config.pits {
             return Err(MancalaError::NoSuchCup);
         }
 
-        match self.board.get_cup(CupPos {
+        match board.get_cup(CupPos {
             owner: player,
             pos: cup,
         }) {
@@ -129,59 +330,86 @@ impl Ayoayo {
             _ => (),
         };
 
-        let must_feed = self.board.starving(player.next_player());
-        let mut test_board = self.clone();
-        test_board.board.new_move();
-        test_board.sow(player, cup)?;
-        if must_feed && test_board.board.starving(player.next_player()) {
-            //If we must feed and didn't, we need to make sure that we couldn't have
-            let any_not_starving = (0..(BOARD_SIZE / 2))
-                .filter(|i| *i != cup)
-                .map(|cup| {
-                    let mut b = self.clone();
-                    if b.sow(player, cup).is_err() {
-                        return self.clone().board;
-                    }
-                    b.board
-                })
-                .map(|board| board.starving(player.next_player()))
-                .any(|s| !s);
-            if any_not_starving {
-                return Err(MancalaError::MustFeedError);
-            }
-        };
-
-        self.board = test_board.board.clone();
-        if test_board.board.starving(player.next_player()) {
-            self.win_state(player);
+        // Feeding check (Must give other player seeds if other player has no seeds _at start of play_)
+        if self.config.must_feed {
+            must_feed(self, board, player, cup, 0..self.config.pits)
         } else {
-            self.state = GameState::InProgress(player.next_player())
-        };
+            Ok(())
+        }
+    }
 
-        Ok(())
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<usize> {
+        (0..self.config.pits)
+            .filter(|cup| self.validate(board, player, *cup).is_ok())
+            .collect()
     }
-}
 
-impl fmt::Display for Ayoayo {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}", self.board)
+    fn sow(&self, board: &mut MancalaBoard, player: Player, cup: usize) -> Result<Pit> {
+        board.pickup(
+            CupPos {
+                owner: player,
+                pos: cup,
+            },
+            player,
+        );
+        let mut last = board.sow(
+            player,
+            CupPos {
+                owner: player,
+                pos: cup,
+            },
+            skip_origin,
+        )?;
+        let mut relays = 0;
+        while let Pit::Cup(cup_pos) = last {
+            if board.seeds(last) <= 1 {
+                break;
+            }
+            relays += 1;
+            if relays > MAX_RELAYS {
+                return Err(MancalaError::IllegalMove);
+            }
+            board.pickup(cup_pos, player);
+            last = board.sow(player, cup_pos, skip_origin)?;
+        }
+        Ok(last)
+    }
+
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit) {
+        match last {
+            Pit::Cup(last) if last.owner == player => {
+                board.pickup(
+                    CupPos {
+                        owner: player.next_player(),
+                        pos: last.pos,
+                    },
+                    player,
+                );
+                board.bank(player);
+            }
+            _ => (),
+        };
     }
-}
 
-impl fmt::Debug for Ayoayo {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}", self.board)
+    // Game over Check (No valid moves)
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, _last: Pit) -> GameState {
+        if board.starving(player.next_player()) {
+            end_game(board, self.collector(player))
+        } else {
+            GameState::InProgress(player.next_player())
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::Game;
 
     #[test]
     #[allow(clippy::cognitive_complexity)]
     fn play() -> Result<()> {
-        let mut board = Ayoayo::new();
+        let mut board = Game::new(Ayoayo::new());
         board.play(3)?;
         assert_eq!("0 - ①|⑥|⑥|②|⑦|①\n⑥|①|⑥|⑥|⑥|⓪ - 0", format!("{}", board));
         assert_eq!(board.state, GameState::InProgress(Player::Player2));
@@ -253,10 +481,19 @@ mod tests {
         Ok(())
     }
 
+    fn three_pits() -> Ayoayo {
+        Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })
+        .unwrap()
+    }
+
     #[test]
     fn must_feed_test() {
-        let mut game = Ayoayo {
-            board: MancalaBoard::new(vec![
+        let mut game = Game::with_board(
+            three_pits(),
+            MancalaBoard::new(vec![
                 Cup {
                     seeds: 1,
                     owner: Player::Player1,
@@ -287,16 +524,19 @@ mod tests {
                     owner: Player::Player2,
                     pos: 2,
                 },
-            ]),
-            state: GameState::InProgress(Player::Player1),
-        };
+            ])
+            .unwrap(),
+            GameState::InProgress(Player::Player1),
+        );
         assert_eq!(Err(MancalaError::MustFeedError), game.play(0));
+        assert_eq!(vec![2], game.legal_moves());
     }
 
     #[test]
     fn no_seeds_test() {
-        let mut game = Ayoayo {
-            board: MancalaBoard::new(vec![
+        let mut game = Game::with_board(
+            three_pits(),
+            MancalaBoard::new(vec![
                 Cup {
                     seeds: 1,
                     owner: Player::Player1,
@@ -327,9 +567,263 @@ mod tests {
                     owner: Player::Player2,
                     pos: 2,
                 },
-            ]),
-            state: GameState::InProgress(Player::Player1),
-        };
+            ])
+            .unwrap(),
+            GameState::InProgress(Player::Player1),
+        );
         assert_eq!(Err(MancalaError::NoSeedsToSow), game.play(1));
     }
+
+    #[test]
+    fn small_board() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            seeds: 2,
+            ..AyoayoConfig::default()
+        })?;
+        let mut game = Game::new(rules);
+        assert_eq!("0 - ②|②|②\n②|②|② - 0", format!("{}", game));
+        assert_eq!(
+            vec![0, 1, 2],
+            rules.legal_moves(game.board(), Player::Player1)
+        );
+        assert_eq!(Err(MancalaError::NoSuchCup), game.play(3));
+        game.play(2)?;
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn bad_config() {
+        let config = |pits, seeds| {
+            Ayoayo::with_config(AyoayoConfig {
+                pits,
+                seeds,
+                ..AyoayoConfig::default()
+            })
+        };
+        assert_eq!(Err(MancalaError::InvalidBoard), config(0, 4));
+        assert_eq!(Err(MancalaError::InvalidBoard), config(MAX_CUPS / 2 + 1, 4));
+        assert_eq!(Err(MancalaError::InvalidBoard), config(6, 0));
+        assert_eq!(Err(MancalaError::InvalidBoard), config(6, 10_000));
+        assert_eq!(Err(MancalaError::InvalidBoard), config(6, usize::MAX));
+        assert!(config(MAX_CUPS / 2, 1).is_ok());
+    }
+
+    #[test]
+    fn leftovers() -> Result<()> {
+        let position = |config: AyoayoConfig| {
+            let mut cups = vec![1, 0, 1]
+                .into_iter()
+                .enumerate()
+                .map(|(pos, seeds)| Cup {
+                    owner: Player::Player1,
+                    seeds,
+                    pos,
+                })
+                .collect::<Vec<_>>();
+            cups.extend((0..3).map(|pos| Cup {
+                owner: Player::Player2,
+                seeds: 0,
+                pos,
+            }));
+            Game::with_board(
+                Ayoayo::with_config(config).unwrap(),
+                MancalaBoard::new(cups).unwrap(),
+                GameState::InProgress(Player::Player1),
+            )
+        };
+        let config = AyoayoConfig {
+            pits: 3,
+            must_feed: false,
+            ..AyoayoConfig::default()
+        };
+
+        let mut game = position(config);
+        game.play(0)?;
+        assert_eq!("2 - ⓪|⓪|⓪\n⓪|⓪|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::Won(Player::Player1));
+
+        let mut game = position(AyoayoConfig {
+            leftovers: Leftovers::Opponent,
+            ..config
+        });
+        game.play(0)?;
+        assert_eq!("0 - ⓪|⓪|⓪\n⓪|⓪|⓪ - 2", format!("{}", game));
+        assert_eq!(game.state, GameState::Won(Player::Player2));
+
+        let mut game = position(AyoayoConfig {
+            leftovers: Leftovers::Nobody,
+            ..config
+        });
+        game.play(0)?;
+        assert_eq!("0 - ⓪|①|①\n⓪|⓪|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::Draw);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_redo() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        let start = game.clone();
+        assert!(!game.undo());
+        game.play(3)?;
+        game.play(0)?;
+        let after = game.clone();
+
+        assert!(game.undo());
+        assert_eq!("0 - ①|⑥|⑥|②|⑦|①\n⑥|①|⑥|⑥|⑥|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        assert!(game.undo());
+        assert_eq!(start.board().cups(), game.board().cups());
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(after.board(), game.board());
+        assert_eq!(after.state, game.state);
+
+        game.undo();
+        game.play(1)?;
+        assert!(!game.redo());
+        Ok(())
+    }
+
+    #[test]
+    fn make_move_matches_game() -> Result<()> {
+        let configs = [
+            AyoayoConfig::default(),
+            AyoayoConfig {
+                must_feed: false,
+                leftovers: Leftovers::Opponent,
+                ..AyoayoConfig::default()
+            },
+            AyoayoConfig {
+                pits: 3,
+                seeds: 2,
+                leftovers: Leftovers::Nobody,
+                ..AyoayoConfig::default()
+            },
+        ];
+        // Plays the same pseudo-random games through `Game::play` and `make_move`.
+        let mut random: u64 = 7;
+        for config in configs.iter() {
+            let rules = Ayoayo::with_config(*config)?;
+            for _ in 0..20 {
+                let mut game = Game::new(rules);
+                let start = game.position().unwrap();
+                let mut position = start;
+                let mut undos = Vec::new();
+                // Games can go round in circles, so they're cut short.
+                for _ in 0..100 {
+                    let moves = game.legal_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    assert_eq!(moves, rules.legal_cups(&position).collect::<Vec<_>>());
+                    random = random
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    let cup = moves[(random >> 33) as usize % moves.len()];
+                    if let Err(err) = game.play(cup) {
+                        let before = position;
+                        assert_eq!(Err(err.clone()), rules.play_move(&position, cup));
+                        assert_eq!(Err(err), rules.make_move(&mut position, cup));
+                        assert_eq!(before, position);
+                        break;
+                    }
+                    let played = rules.play_move(&position, cup)?;
+                    undos.push(rules.make_move(&mut position, cup)?);
+                    assert_eq!(played, position);
+                    assert_eq!(
+                        Position::from_board(game.board(), position.to_move)?,
+                        position
+                    );
+                    assert_eq!(Ok(game.state.clone()), rules.outcome(&position));
+                }
+                while let Some(undo) = undos.pop() {
+                    rules.unmake_move(&mut position, undo);
+                }
+                assert_eq!(start, position);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_board() -> Result<()> {
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        let rules = Ayoayo::new();
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            rules.side(&position, Player::Player2)
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            rules.starving(&position, Player::Player1)
+        );
+        assert_eq!(Err(MancalaError::InvalidBoard), rules.outcome(&position));
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            rules.play_move(&position, 0)
+        );
+        assert_eq!(
+            Ok(GameState::InProgress(Player::Player1)),
+            three_pits().outcome(&position)
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        game.play(3)?;
+        game.play(0)?;
+        game.undo();
+
+        let json = serde_json::to_string(&game).unwrap();
+        let restored: Game<Ayoayo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(game, restored);
+
+        let bytes = bincode::serialize(&game).unwrap();
+        let mut restored: Game<Ayoayo> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(game, restored);
+        assert!(restored.redo());
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_checks_turns() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        game.play(3)?;
+        let json = serde_json::to_string(&game).unwrap();
+        let turn = r#"{"state":{"InProgress":"Player1"},"play":{"One":3}}"#;
+        let padded = json.replace(
+            &format!(r#""history":[{}]"#, turn),
+            &format!(r#""history":[{},{},{}]"#, turn, turn, turn),
+        );
+        assert_ne!(json, padded);
+        assert!(serde_json::from_str::<Game<Ayoayo>>(&padded).is_err());
+
+        let won = json.replace(
+            r#""state":{"InProgress":"Player2"}"#,
+            r#""state":{"Won":"Player1"}"#,
+        );
+        assert_ne!(json, won);
+        assert!(serde_json::from_str::<Game<Ayoayo>>(&won).is_err());
+
+        let too_big = json.replace(r#""pits":6"#, r#""pits":100"#);
+        assert_ne!(json, too_big);
+        assert!(serde_json::from_str::<Game<Ayoayo>>(&too_big).is_err());
+        let smaller = json.replace(r#""pits":6"#, r#""pits":3"#);
+        assert!(serde_json::from_str::<Game<Ayoayo>>(&smaller).is_err());
+
+        let other_move = json.replace(r#""play":{"One":3}"#, r#""play":{"One":2}"#);
+        assert_ne!(json, other_move);
+        assert!(serde_json::from_str::<Game<Ayoayo>>(&other_move).is_err());
+        Ok(())
+    }
 }
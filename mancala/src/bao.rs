@@ -0,0 +1,564 @@
+//! Bao la Kiswahili, played on a four row board.
+//!
+//! Each player starts with 22 seeds in their store, 6 in their nyumba (house)
+//! and 2 in each of the two cups to its right. While a player still has seeds
+//! in their store they are in the namua phase: every turn one seed comes out
+//! of the store and is put in a non-empty cup of their inner row. Once the
+//! store is empty they are in the mtaji phase and sow from any cup of theirs
+//! holding at least two seeds.
+//!
+//! A move captures when its sowing ends in an occupied cup of the inner row
+//! that faces an occupied cup of the opponent's. The opponent's seeds are
+//! sown in again from a kichwa, an end of the inner row, and the sowing
+//! carries on from wherever they finish. Sowing that ends in any other
+//! occupied cup picks it up and keeps going; it stops in an empty cup. If a
+//! capture is possible it has to be made, otherwise the move is a takasa and
+//! captures nothing at all.
+//!
+//! The nyumba stays a house until it's emptied. A takasa from your own house
+//! is only allowed when nothing else can be played. In the namua phase such a
+//! takasa only takes two seeds out of it, and a sowing that ends in your own
+//! house stops there.
+//!
+//! A player loses when their inner row is empty or they have no legal move.
+
+use crate::board::{Cup, CupPos, MancalaBoard, Pit};
+use crate::game::{Ruleset, MAX_RELAYS};
+use crate::topology::{Direction, Topology};
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const PITS: usize = 8;
+const HOUSE: usize = 4;
+const STARTING_STORE: usize = 22;
+const MAX_CAPTURE_SEEDS: usize = 15;
+const HOUSE_TAX: usize = 2;
+
+/// An end of a player's inner row, where captured seeds are sown back in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Kichwa {
+    Left,
+    Right,
+}
+
+impl Kichwa {
+    fn cup(self, player: Player) -> CupPos {
+        match self {
+            Kichwa::Left => CupPos {
+                owner: player,
+                pos: 0,
+            },
+            Kichwa::Right => CupPos {
+                owner: player,
+                pos: PITS - 1,
+            },
+        }
+    }
+
+    // Captured seeds always head from the kichwa towards the middle of the row.
+    fn direction(self) -> Direction {
+        match self {
+            Kichwa::Left => Direction::Clockwise,
+            Kichwa::Right => Direction::Anticlockwise,
+        }
+    }
+
+    fn heading(direction: Direction) -> Kichwa {
+        match direction {
+            Direction::Clockwise => Kichwa::Left,
+            Direction::Anticlockwise => Kichwa::Right,
+        }
+    }
+
+    // Captures next to an end (a kimbi) always come in at that end.
+    fn forced(pos: usize) -> Option<Kichwa> {
+        match pos {
+            0 | 1 => Some(Kichwa::Left),
+            p if p == PITS - 2 || p == PITS - 1 => Some(Kichwa::Right),
+            _ => None,
+        }
+    }
+}
+
+/// A cup to play and which way to sow it.
+///
+/// In the namua phase `pit` is the inner row cup the seed from the store goes
+/// into. When that captures, `direction` is the way the captured seeds travel,
+/// and if the capture is in the middle of the row `kichwa` says which end they
+/// come in at. `kichwa` must be `None` for every other move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BaoMove {
+    pub pit: usize,
+    pub direction: Direction,
+    pub kichwa: Option<Kichwa>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bao;
+
+impl Bao {
+    pub fn new() -> Bao {
+        Bao
+    }
+
+    fn cup(player: Player, pos: usize) -> CupPos {
+        CupPos { owner: player, pos }
+    }
+
+    fn seeds(board: &MancalaBoard, player: Player, pos: usize) -> usize {
+        board.seeds(Pit::Cup(Bao::cup(player, pos)))
+    }
+
+    fn namua(board: &MancalaBoard, player: Player) -> bool {
+        board.get_bank(player) > 0
+    }
+
+    fn inner_row_empty(board: &MancalaBoard, player: Player) -> bool {
+        (0..PITS).all(|pos| Bao::seeds(board, player, pos) == 0)
+    }
+
+    // An occupied inner row cup facing an occupied cup. Once the last seed
+    // has landed, "occupied" means it holds more than that seed.
+    fn faces_seeds(board: &MancalaBoard, cup: CupPos, before: usize) -> bool {
+        board.topology().is_inner(cup)
+            && board.seeds(Pit::Cup(cup)) > before
+            && board
+                .topology()
+                .opposite(cup)
+                .is_some_and(|opposite| board.seeds(Pit::Cup(opposite)) > 0)
+    }
+
+    fn both_ways(pos: usize) -> Vec<BaoMove> {
+        vec![
+            BaoMove {
+                pit: pos,
+                direction: Direction::Clockwise,
+                kichwa: None,
+            },
+            BaoMove {
+                pit: pos,
+                direction: Direction::Anticlockwise,
+                kichwa: None,
+            },
+        ]
+    }
+
+    // Whether sowing `pos` in the mtaji phase ends somewhere it can capture.
+    fn mtaji_captures(
+        board: &MancalaBoard,
+        player: Player,
+        pos: usize,
+        direction: Direction,
+    ) -> bool {
+        let seeds = Bao::seeds(board, player, pos);
+        if !(2..=MAX_CAPTURE_SEEDS).contains(&seeds) {
+            return false;
+        }
+        let mut board = board.clone();
+        let cup = Bao::cup(player, pos);
+        board.pickup(cup, player);
+        match board.sow_towards(player, cup, direction, |_, _, _| true) {
+            Ok(Pit::Cup(last)) => Bao::faces_seeds(&board, last, 1),
+            _ => false,
+        }
+    }
+
+    fn captures(&self, board: &MancalaBoard, player: Player) -> Vec<BaoMove> {
+        if Bao::namua(board, player) {
+            (0..PITS)
+                .filter(|pos| Bao::faces_seeds(board, Bao::cup(player, *pos), 0))
+                .flat_map(|pos| match Kichwa::forced(pos) {
+                    Some(kichwa) => vec![BaoMove {
+                        pit: pos,
+                        direction: kichwa.direction(),
+                        kichwa: None,
+                    }],
+                    None => [Kichwa::Left, Kichwa::Right]
+                        .iter()
+                        .map(|kichwa| BaoMove {
+                            pit: pos,
+                            direction: kichwa.direction(),
+                            kichwa: Some(*kichwa),
+                        })
+                        .collect(),
+                })
+                .collect()
+        } else {
+            (0..2 * PITS)
+                .flat_map(Bao::both_ways)
+                .filter(|play| Bao::mtaji_captures(board, player, play.pit, play.direction))
+                .collect()
+        }
+    }
+
+    fn takasa(&self, board: &MancalaBoard, player: Player) -> Vec<BaoMove> {
+        let least = if Bao::namua(board, player) { 1 } else { 2 };
+        let playable = |cups: std::ops::Range<usize>| {
+            cups.filter(|pos| Bao::seeds(board, player, *pos) >= least)
+                .collect::<Vec<_>>()
+        };
+        let mut cups = playable(0..PITS);
+        if cups.is_empty() && !Bao::namua(board, player) {
+            cups = playable(PITS..2 * PITS);
+        }
+        let house = Bao::cup(player, HOUSE);
+        if cups.len() > 1 && board.is_house(house) {
+            cups.retain(|pos| *pos != HOUSE);
+        }
+        cups.into_iter().flat_map(Bao::both_ways).collect()
+    }
+
+    fn capture_into(
+        &self,
+        board: &mut MancalaBoard,
+        player: Player,
+        cup: CupPos,
+        kichwa: Kichwa,
+    ) -> Result<Pit> {
+        let opposite = board
+            .topology()
+            .opposite(cup)
+            .ok_or(MancalaError::IllegalMove)?;
+        board.pickup(opposite, player);
+        board.sow_into(player, kichwa.cup(player), kichwa.direction())
+    }
+
+    // Keeps a move going from where its last seed landed until it stops in an empty cup.
+    fn carry_on(
+        &self,
+        board: &mut MancalaBoard,
+        player: Player,
+        mut last: Pit,
+        mut direction: Direction,
+        capturing: bool,
+        namua: bool,
+    ) -> Result<Pit> {
+        let mut relays = 0;
+        while let Pit::Cup(cup) = last {
+            if board.seeds(last) <= 1 {
+                break;
+            }
+            relays += 1;
+            if relays > MAX_RELAYS {
+                return Err(MancalaError::IllegalMove);
+            }
+            if capturing && Bao::faces_seeds(board, cup, 1) {
+                let kichwa = Kichwa::forced(cup.pos).unwrap_or_else(|| Kichwa::heading(direction));
+                last = self.capture_into(board, player, cup, kichwa)?;
+                direction = kichwa.direction();
+            } else if namua && board.is_house(cup) {
+                break;
+            } else {
+                board.pickup(cup, player);
+                last = board.sow_towards(player, cup, direction, |_, _, _| true)?;
+            }
+        }
+        Ok(last)
+    }
+}
+
+impl Ruleset for Bao {
+    type Move = BaoMove;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..2 * PITS).map(move |i| Cup {
+                    owner: *player,
+                    seeds: match i {
+                        HOUSE => 6,
+                        5 | 6 => 2,
+                        _ => 0,
+                    },
+                    pos: i,
+                })
+            })
+            .collect();
+
+        let mut board = MancalaBoard::with_topology(board, Topology::FourRow { pits: PITS })
+            .expect("a Bao board fits");
+        for player in [Player::Player1, Player::Player2].iter() {
+            board.fill_store(*player, STARTING_STORE);
+            board.add_house(Bao::cup(*player, HOUSE));
+        }
+        board
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, play: BaoMove) -> Result<()> {
+        let namua = Bao::namua(board, player);
+        let cups = if namua { PITS } else { 2 * PITS };
+        if play.pit >= cups {
+            return Err(MancalaError::NoSuchCup);
+        }
+        let least = if namua { 1 } else { 2 };
+        if Bao::seeds(board, player, play.pit) < least {
+            return Err(MancalaError::NoSeedsToSow);
+        }
+
+        let captures = self.captures(board, player);
+        if captures.contains(&play) {
+            Ok(())
+        } else if captures.iter().any(|capture| capture.pit == play.pit) {
+            Err(MancalaError::IllegalMove)
+        } else if !captures.is_empty() {
+            Err(MancalaError::MustCapture)
+        } else if self.takasa(board, player).contains(&play) {
+            Ok(())
+        } else {
+            Err(MancalaError::IllegalMove)
+        }
+    }
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<BaoMove> {
+        let captures = self.captures(board, player);
+        if captures.is_empty() {
+            self.takasa(board, player)
+        } else {
+            captures
+        }
+    }
+
+    fn sow(&self, board: &mut MancalaBoard, player: Player, play: BaoMove) -> Result<Pit> {
+        let cup = Bao::cup(player, play.pit);
+        if Bao::namua(board, player) {
+            board.draw(player, 1)?;
+            board.sow_into(player, cup, play.direction)?;
+            if Bao::faces_seeds(board, cup, 1) {
+                let kichwa = play
+                    .kichwa
+                    .or_else(|| Kichwa::forced(play.pit))
+                    .ok_or(MancalaError::IllegalMove)?;
+                let last = self.capture_into(board, player, cup, kichwa)?;
+                return self.carry_on(board, player, last, kichwa.direction(), true, true);
+            }
+            if board.is_house(cup) {
+                board.take(cup, HOUSE_TAX, player)?;
+            } else {
+                board.pickup(cup, player);
+            }
+            let last = board.sow_towards(player, cup, play.direction, |_, _, _| true)?;
+            self.carry_on(board, player, last, play.direction, false, true)
+        } else {
+            let capturing = Bao::mtaji_captures(board, player, play.pit, play.direction);
+            board.pickup(cup, player);
+            let last = board.sow_towards(player, cup, play.direction, |_, _, _| true)?;
+            self.carry_on(board, player, last, play.direction, capturing, false)
+        }
+    }
+
+    // Captures happen part way through sowing, so there's nothing left to do here.
+    fn capture(&self, _board: &mut MancalaBoard, _player: Player, _last: Pit) {}
+
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, _last: Pit) -> GameState {
+        let opponent = player.next_player();
+        if Bao::inner_row_empty(board, opponent) || self.legal_moves(board, opponent).is_empty() {
+            GameState::Won(player)
+        } else if Bao::inner_row_empty(board, player) {
+            GameState::Won(opponent)
+        } else {
+            GameState::InProgress(opponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn position(player1: &[(usize, usize)], player2: &[(usize, usize)], store: usize) -> Game<Bao> {
+        let row = |owner: Player, seeds: &[(usize, usize)]| {
+            (0..2 * PITS)
+                .map(|pos| Cup {
+                    owner,
+                    seeds: seeds
+                        .iter()
+                        .find(|(at, _)| *at == pos)
+                        .map_or(0, |(_, seeds)| *seeds),
+                    pos,
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut cups = row(Player::Player1, player1);
+        cups.extend(row(Player::Player2, player2));
+        let mut board =
+            MancalaBoard::with_topology(cups, Topology::FourRow { pits: PITS }).unwrap();
+        board.fill_store(Player::Player1, store);
+        board.fill_store(Player::Player2, store);
+        Game::with_board(Bao::new(), board, GameState::InProgress(Player::Player1))
+    }
+
+    fn seeds(game: &Game<Bao>, player: Player) -> Vec<usize> {
+        game.get_cups_for_player(player)
+            .iter()
+            .map(|cup| cup.seeds)
+            .collect()
+    }
+
+    #[test]
+    fn namua_takasa() -> Result<()> {
+        let mut game = Game::new(Bao::new());
+        assert_eq!(
+            [Bao::both_ways(5), Bao::both_ways(6)].concat(),
+            game.rules().legal_moves(game.board(), Player::Player1)
+        );
+        game.play(BaoMove {
+            pit: 5,
+            direction: Direction::Clockwise,
+            kichwa: None,
+        })?;
+        assert_eq!(
+            "21 - ⓪|⓪|⓪|⓪|⓪|⓪|⓪|①\n⓪|⓪|⓪|⓪|⑥|⓪|③|①\n⓪|②|②|⑥|⓪|⓪|⓪|⓪\n⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪ - 22",
+            format!("{}", game)
+        );
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn namua_capture_from_kimbi() -> Result<()> {
+        let mut game = position(&[(1, 1), (3, 1)], &[(0, 1), (6, 3)], 5);
+        let play = BaoMove {
+            pit: 1,
+            direction: Direction::Clockwise,
+            kichwa: None,
+        };
+        assert_eq!(
+            vec![play],
+            game.rules().legal_moves(game.board(), Player::Player1)
+        );
+        assert_eq!(
+            Err(MancalaError::MustCapture),
+            game.play(BaoMove { pit: 3, ..play })
+        );
+        assert_eq!(
+            Err(MancalaError::IllegalMove),
+            game.play(BaoMove {
+                direction: Direction::Anticlockwise,
+                ..play
+            })
+        );
+        assert_eq!(
+            Err(MancalaError::IllegalMove),
+            game.play(BaoMove {
+                kichwa: Some(Kichwa::Left),
+                ..play
+            })
+        );
+        game.play(play)?;
+        assert_eq!(
+            vec![1, 3, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            seeds(&game, Player::Player1)
+        );
+        assert_eq!(4, game.get_bank(Player::Player1));
+        assert_eq!(0, seeds(&game, Player::Player2)[6]);
+        Ok(())
+    }
+
+    #[test]
+    fn namua_capture_needs_kichwa() -> Result<()> {
+        let mut game = position(&[(3, 1)], &[(0, 1), (4, 2)], 5);
+        assert_eq!(
+            Err(MancalaError::IllegalMove),
+            game.play(BaoMove {
+                pit: 3,
+                direction: Direction::Anticlockwise,
+                kichwa: None,
+            })
+        );
+        game.play(BaoMove {
+            pit: 3,
+            direction: Direction::Anticlockwise,
+            kichwa: Some(Kichwa::Right),
+        })?;
+        assert_eq!(
+            vec![0, 0, 0, 2, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+            seeds(&game, Player::Player1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mtaji_capture_and_relay() -> Result<()> {
+        let mut game = position(&[(0, 2), (2, 1)], &[(0, 1), (5, 3)], 0);
+        assert_eq!(
+            Err(MancalaError::NoSeedsToSow),
+            game.play(BaoMove {
+                pit: 2,
+                direction: Direction::Clockwise,
+                kichwa: None,
+            })
+        );
+        game.play(BaoMove {
+            pit: 0,
+            direction: Direction::Clockwise,
+            kichwa: None,
+        })?;
+        assert_eq!(
+            vec![1, 2, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            seeds(&game, Player::Player1)
+        );
+        assert_eq!(game.state, GameState::Won(Player::Player1));
+        Ok(())
+    }
+
+    #[test]
+    fn house_is_taxed() -> Result<()> {
+        let mut game = position(&[(HOUSE, 6)], &[(0, 1)], 5);
+        game.board.add_house(Bao::cup(Player::Player1, HOUSE));
+        game.play(BaoMove {
+            pit: HOUSE,
+            direction: Direction::Clockwise,
+            kichwa: None,
+        })?;
+        assert_eq!(
+            vec![0, 0, 0, 0, 5, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            seeds(&game, Player::Player1)
+        );
+        assert!(game.board().is_house(Bao::cup(Player::Player1, HOUSE)));
+
+        assert!(game.undo());
+        assert_eq!(
+            vec![0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            seeds(&game, Player::Player1)
+        );
+        assert_eq!(5, game.get_bank(Player::Player1));
+        assert!(game.board().is_house(Bao::cup(Player::Player1, HOUSE)));
+        Ok(())
+    }
+
+    #[test]
+    fn endless_relay() {
+        let start = [3, 4, 3, 4, 0, 1, 0, 1, 3, 2, 1, 0, 4, 3, 0, 1];
+        let cups: Vec<_> = start.iter().copied().enumerate().collect();
+        let mut game = position(&cups, &[(12, 1)], 0);
+        // This sowing never reaches an empty cup.
+        assert_eq!(
+            Err(MancalaError::IllegalMove),
+            game.play(BaoMove {
+                pit: 3,
+                direction: Direction::Clockwise,
+                kichwa: None,
+            })
+        );
+        assert_eq!(start.to_vec(), seeds(&game, Player::Player1));
+    }
+
+    #[test]
+    fn no_such_cup() {
+        let mut game = Game::new(Bao::new());
+        assert_eq!(
+            Err(MancalaError::NoSuchCup),
+            game.play(BaoMove {
+                pit: PITS,
+                direction: Direction::Clockwise,
+                kichwa: None,
+            })
+        );
+    }
+}
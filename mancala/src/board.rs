@@ -1,15 +1,43 @@
+use crate::topology::{Direction, Topology};
+use crate::zobrist;
 use crate::{MancalaError, Player, Result};
 use itertools::Itertools;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, PartialEq, Clone)]
+/// The most cups a board can have, enough for a Bao board.
+pub const MAX_CUPS: usize = 32;
+
+#[derive(Copy, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cup {
     pub owner: Player,
     pub seeds: usize,
     pub pos: usize,
 }
 
+#[cfg(test)]
+impl Cup {
+    /// Player 1's row then Player 2's, holding the given seeds.
+    pub(crate) fn rows(player1: &[usize], player2: &[usize]) -> Vec<Cup> {
+        let row = |owner, seeds: &[usize]| {
+            seeds
+                .iter()
+                .enumerate()
+                .map(move |(pos, seeds)| Cup {
+                    owner,
+                    seeds: *seeds,
+                    pos,
+                })
+                .collect::<Vec<_>>()
+        };
+        [row(Player::Player1, player1), row(Player::Player2, player2)].concat()
+    }
+}
+
 #[derive(Copy, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CupPos {
     pub owner: Player,
     pub pos: usize,
@@ -34,25 +62,20 @@ impl From<&Cup> for CupPos {
     }
 }
 
-impl std::convert::Into<CupPos> for Cup {
-    fn into(self) -> CupPos {
-        CupPos {
-            owner: self.owner,
-            pos: self.pos,
-        }
+impl From<Cup> for CupPos {
+    fn from(cup: Cup) -> Self {
+        CupPos::from(&cup)
     }
 }
 
-impl std::convert::Into<CupPos> for &mut Cup {
-    fn into(self) -> CupPos {
-        CupPos {
-            owner: self.owner,
-            pos: self.pos,
-        }
+impl From<&mut Cup> for CupPos {
+    fn from(cup: &mut Cup) -> Self {
+        CupPos::from(&*cup)
     }
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Bank {
     player1: usize,
     player2: usize,
@@ -79,6 +102,15 @@ impl Bank {
         }
     }
 
+    fn withdraw(&mut self, player: Player, count: usize) -> Option<usize> {
+        let banked = match player {
+            Player::Player1 => &mut self.player1,
+            Player::Player2 => &mut self.player2,
+        };
+        *banked = banked.checked_sub(count)?;
+        Some(*banked)
+    }
+
     pub fn get(&self, player: Player) -> usize {
         match player {
             Player::Player1 => self.player1,
@@ -88,6 +120,7 @@ impl Bank {
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InHand {
     player1: usize,
     player2: usize,
@@ -137,126 +170,691 @@ impl InHand {
     }
 }
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Move {
     Pickup(CupPos),
     Place(CupPos),
     Bank(Player, usize),
+    /// Some of a cup's seeds were picked up, but not all of them.
+    Take(CupPos, usize),
+    /// Several seeds were sown into a cup at once.
+    Sow(CupPos, usize),
+    /// Seeds were taken out of a player's store to be played.
+    Draw(Player, usize),
+    /// A cup became a house.
+    House(CupPos),
+    /// A cup became a tuzdyk feeding a player's store.
+    Tuzdyk(CupPos, Player),
+}
+
+/// Sowing filter that never drops a seed back into the cup it was picked up from.
+pub fn skip_origin(check_cup: &CupPos, player: Player, start_cup: usize) -> bool {
+    let cup = check_cup;
+    !(cup.owner == player && cup.pos == start_cup)
+}
+
+/// A position seeds can be sown into: a cup, or a store that feeds a player's bank.
+#[derive(Copy, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Pit {
+    Cup(CupPos),
+    Store(Player),
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "BoardData", into = "BoardData")
+)]
 pub struct MancalaBoard {
-    pub(crate) cups: Vec<Cup>,
+    // Seeds in each cup, indexed by `index`: Player 1's cups in sowing order
+    // and then Player 2's.
+    seeds: [usize; MAX_CUPS],
+    pub(crate) topology: Topology,
+    pub(crate) houses: Vec<CupPos>,
+    pub(crate) tuzdyks: Vec<(CupPos, Player)>,
     pub(crate) bank: Bank,
     pub(crate) in_hand: InHand,
     pub(crate) moves: Vec<Vec<Move>>,
+    pub(crate) undone: Vec<Vec<Move>>,
+    seed_log: bool,
+    // The Zobrist key of the cups and banks, see `zobrist`.
+    hash: u64,
+}
+
+// What a board is serialized as, and deserializes from before it's checked
+// against its move log.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct BoardData {
+    cups: Vec<Cup>,
+    topology: Topology,
+    houses: Vec<CupPos>,
+    tuzdyks: Vec<(CupPos, Player)>,
+    bank: Bank,
+    in_hand: InHand,
+    moves: Vec<Vec<Move>>,
+    undone: Vec<Vec<Move>>,
+    #[serde(default)]
+    seed_log: bool,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<BoardData> for MancalaBoard {
+    type Error = MancalaError;
+
+    fn try_from(data: BoardData) -> Result<MancalaBoard> {
+        let layout = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| data.topology.cups(*player));
+        if 2 * data.topology.cups_per_player() > MAX_CUPS
+            || !data.cups.iter().map(CupPos::from).eq(layout)
+        {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let mut seeds = [0; MAX_CUPS];
+        for (index, cup) in data.cups.iter().enumerate() {
+            seeds[index] = cup.seeds;
+        }
+        let mut board = MancalaBoard {
+            seeds,
+            topology: data.topology,
+            houses: data.houses,
+            tuzdyks: data.tuzdyks,
+            bank: data.bank,
+            in_hand: data.in_hand,
+            moves: data.moves,
+            undone: data.undone,
+            seed_log: data.seed_log,
+            hash: 0,
+        };
+        board.hash = board.rehash();
+        board.check()?;
+        Ok(board)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<MancalaBoard> for BoardData {
+    fn from(board: MancalaBoard) -> BoardData {
+        BoardData {
+            cups: board.cups(),
+            topology: board.topology,
+            houses: board.houses,
+            tuzdyks: board.tuzdyks,
+            bank: board.bank,
+            in_hand: board.in_hand,
+            moves: board.moves,
+            undone: board.undone,
+            seed_log: board.seed_log,
+        }
+    }
 }
 
 impl MancalaBoard {
     // Does the board need the concept of the bank and the hand?
-    pub(crate) fn new(cups: Vec<Cup>) -> MancalaBoard {
+    pub fn new(cups: Vec<Cup>) -> Result<MancalaBoard> {
+        let pits = cups.len() / 2;
+        MancalaBoard::with_topology(
+            cups,
+            Topology::TwoRow {
+                pits,
+                stores: false,
+            },
+        )
+    }
+
+    /// Builds a board whose sowing ring has each player's store after their last cup.
+    pub fn with_stores(cups: Vec<Cup>) -> Result<MancalaBoard> {
+        let pits = cups.len() / 2;
+        MancalaBoard::with_topology(cups, Topology::TwoRow { pits, stores: true })
+    }
+
+    /// Fails with `InvalidBoard` if the topology has more than `MAX_CUPS`
+    /// cups, or a cup isn't on it.
+    pub fn with_topology(cups: Vec<Cup>, topology: Topology) -> Result<MancalaBoard> {
+        if 2 * topology.cups_per_player() > MAX_CUPS {
+            return Err(MancalaError::InvalidBoard);
+        }
         let mut board = MancalaBoard {
-            cups: cups.clone().into_iter().map(|c| c).collect(),
+            seeds: [0; MAX_CUPS],
+            topology,
+            houses: Vec::new(),
+            tuzdyks: Vec::new(),
             bank: Bank::new(),
             in_hand: InHand::new(),
             moves: Vec::new(),
+            undone: Vec::new(),
+            seed_log: false,
+            hash: 0,
         };
+        for cup in cups.iter() {
+            board
+                .change(CupPos::from(cup), |_| Some(cup.seeds))
+                .ok_or(MancalaError::InvalidBoard)?;
+        }
         board.moves.push(
             cups.iter()
-                .flat_map(|cup| (0..cup.seeds).map(move |_| Move::Place(cup.clone().into())))
+                .flat_map(|cup| (0..cup.seeds).map(move |_| Move::Place(CupPos::from(cup))))
                 .collect(),
         );
-        board
+        Ok(board)
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Puts seeds straight into a player's store before play starts.
+    pub fn fill_store(&mut self, player: Player, seeds: usize) {
+        self.in_hand.take(player, seeds);
+        self.bank(player);
+    }
+
+    /// Marks `cup` as a house. It stays one until all of its seeds are picked up.
+    pub fn add_house(&mut self, cup: CupPos) {
+        self.houses.push(cup);
+        self.log(Move::House(cup));
     }
 
-    pub(crate) fn new_move(&mut self) {
-        self.moves.push(Vec::new())
+    pub fn is_house(&self, cup: CupPos) -> bool {
+        self.houses.contains(&cup)
     }
 
-    pub(crate) fn get_cup(&self, cup: CupPos) -> Option<&Cup> {
-        for c in self.cups.iter() {
-            if *c == cup {
-                return Some(c);
+    /// Turns `cup` into a tuzdyk: from now on every seed sown into it goes
+    /// straight to `player`'s store.
+    pub fn add_tuzdyk(&mut self, cup: CupPos, player: Player) {
+        self.tuzdyks.push((cup, player));
+        self.log(Move::Tuzdyk(cup, player));
+    }
+
+    /// The player whose store `cup` feeds, if it is a tuzdyk.
+    pub fn tuzdyk(&self, cup: CupPos) -> Option<Player> {
+        self.tuzdyks
+            .iter()
+            .find(|(tuzdyk, _)| *tuzdyk == cup)
+            .map(|(_, player)| *player)
+    }
+
+    /// Logs every seed sown as its own move, so a turn can be replayed seed
+    /// by seed. Otherwise a sowing logs one move for each pit it reaches.
+    pub fn log_each_seed(&mut self, on: bool) {
+        self.seed_log = on;
+    }
+
+    /// A copy of the board without its move log, which then logs nothing
+    /// more. It can't be undone or replayed, but it's cheap to copy.
+    pub(crate) fn without_log(&self) -> MancalaBoard {
+        MancalaBoard {
+            moves: Vec::new(),
+            undone: Vec::new(),
+            seed_log: false,
+            ..self.clone()
+        }
+    }
+
+    pub fn new_move(&mut self) {
+        self.moves.push(Vec::new());
+        self.undone.clear();
+    }
+
+    /// Rewinds the last turn by replaying every turn before it onto an empty
+    /// board. The board as it was set up can't be undone.
+    pub fn undo_move(&mut self) -> bool {
+        if self.moves.len() <= 1 {
+            return false;
+        }
+        if let Some(turn) = self.moves.pop() {
+            self.undone.push(turn);
+        }
+        self.rebuild()
+            .expect("the move log was played on this board");
+        true
+    }
+
+    /// Plays the last undone turn again.
+    pub fn redo_move(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(turn) => {
+                for played in turn.iter() {
+                    self.apply(played)
+                        .expect("undone turns were played on this board");
+                }
+                self.moves.push(turn);
+                true
             }
+            None => false,
         }
-        None
     }
 
-    fn get_mut_cup(&mut self, cup: CupPos) -> Option<&mut Cup> {
-        for c in self.cups.iter_mut() {
-            if *c == cup {
-                return Some(c);
+    // Clears the board and plays every logged turn back onto it, checking
+    // that no turn gains or loses seeds.
+    fn rebuild(&mut self) -> Option<()> {
+        self.seeds = [0; MAX_CUPS];
+        self.houses.clear();
+        self.tuzdyks.clear();
+        self.bank = Bank::new();
+        self.hash = 0;
+        self.in_hand = InHand::new();
+        let mut total = None;
+        for turn in self.moves.clone().iter() {
+            for played in turn {
+                self.apply(played)?;
+            }
+            if *total.get_or_insert(self.total()) != self.total() {
+                return None;
             }
         }
-        None
+        Some(())
     }
 
-    pub(crate) fn starving(&self, player: Player) -> bool {
-        self.cups
+    // Whether the board is what its move log says it is, with undone turns
+    // that can still be redone.
+    #[cfg(feature = "serde")]
+    fn check(&self) -> Result<()> {
+        let mut rebuilt = self.clone();
+        rebuilt.rebuild().ok_or(MancalaError::InvalidBoard)?;
+        if rebuilt.seeds != self.seeds
+            || rebuilt.bank != self.bank
+            || rebuilt.in_hand != self.in_hand
+            || rebuilt.houses != self.houses
+            || rebuilt.tuzdyks != self.tuzdyks
+        {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let total = rebuilt.total();
+        for turn in self.undone.iter().rev() {
+            for played in turn {
+                rebuilt.apply(played).ok_or(MancalaError::InvalidBoard)?;
+            }
+            if rebuilt.total() != total {
+                return Err(MancalaError::InvalidBoard);
+            }
+        }
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.seeds.iter().sum::<usize>()
+            + self.bank.get(Player::Player1)
+            + self.bank.get(Player::Player2)
+            + self.in_hand.get(Player::Player1)
+            + self.in_hand.get(Player::Player2)
+    }
+
+    /// Steps through `turn` from this board, yielding the board after each
+    /// move. Seeds that have been picked up and not yet put down are in
+    /// hand, so they don't show on the yielded boards.
+    pub fn replay<'a>(&self, turn: &'a [Move]) -> Replay<'a> {
+        let hand = turn
             .iter()
-            .filter(|cup| cup.owner == player)
-            .all(|cup| cup.seeds == 0)
+            .find_map(|played| match *played {
+                Move::Pickup(cup) | Move::Take(cup, _) => Some(cup.owner),
+                Move::Draw(player, _) => Some(player),
+                _ => None,
+            })
+            .unwrap_or(Player::Player1);
+        Replay {
+            board: self.clone(),
+            turn: turn.iter(),
+            hand,
+        }
+    }
+
+    /// Steps through the last turn, starting from the board as it was before it.
+    pub fn replay_last_turn(&self) -> Option<Replay<'_>> {
+        if self.moves.len() <= 1 {
+            return None;
+        }
+        let mut before = self.clone();
+        before.undo_move();
+        self.moves.last().map(|turn| before.replay(turn))
     }
 
-    pub(crate) fn pickup(&mut self, cup: CupPos, player: Player) -> Option<()> {
-        let mut cup = self.get_mut_cup(cup);
+    /// Everything that has happened on the board, a turn at a time. The first
+    /// turn is the board being set up.
+    pub fn moves(&self) -> &[Vec<Move>] {
+        &self.moves
+    }
 
-        cup.take()
-            .map(|cup| {
-                let seeds = cup.seeds;
-                cup.seeds = 0;
-                (seeds, cup.clone().into())
+    // Applies a logged move without logging it again, or returns None if it
+    // doesn't fit the board.
+    fn apply(&mut self, played: &Move) -> Option<()> {
+        match *played {
+            Move::Pickup(cup) => {
+                self.change(cup, |_| Some(0))?;
+                self.houses.retain(|house| *house != cup);
+            }
+            Move::Place(cup) => {
+                self.change(cup, |left| Some(left + 1))?;
+            }
+            Move::Sow(cup, seeds) => {
+                self.change(cup, |left| Some(left + seeds))?;
+            }
+            Move::Bank(player, seeds) => self.deposit(player, seeds),
+            Move::Take(cup, seeds) => {
+                self.change(cup, |left| left.checked_sub(seeds))?;
+            }
+            Move::Draw(player, seeds) => self.withdraw(player, seeds)?,
+            Move::House(cup) => {
+                self.get_cup(cup)?;
+                self.houses.push(cup);
+            }
+            Move::Tuzdyk(cup, player) => {
+                self.get_cup(cup)?;
+                self.tuzdyks.push((cup, player));
+            }
+        }
+        Some(())
+    }
+
+    fn log(&mut self, played: Move) {
+        if let Some(moves) = self.moves.last_mut() {
+            moves.push(played)
+        }
+    }
+
+    /// Every cup on the board, Player 1's in sowing order and then Player 2's.
+    pub fn cups(&self) -> Vec<Cup> {
+        [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| self.topology.cups(*player))
+            .zip(self.seeds.iter())
+            .map(|(cup, seeds)| Cup {
+                owner: cup.owner,
+                seeds: *seeds,
+                pos: cup.pos,
             })
-            .map(|res: (usize, CupPos)| {
-                self.in_hand.take(player, res.0);
-                if res.0 > 0 {
-                    if let Some(moves) = self.moves.last_mut() {
-                        moves.push(Move::Pickup(res.1))
-                    }
-                }
+            .collect()
+    }
+
+    // Where `cup` is in `seeds`, if it's on the board.
+    fn index(&self, cup: CupPos) -> Option<usize> {
+        let per_player = self.topology.cups_per_player();
+        if cup.pos >= per_player {
+            return None;
+        }
+        Some(match cup.owner {
+            Player::Player1 => cup.pos,
+            Player::Player2 => per_player + cup.pos,
+        })
+    }
+
+    // Sets the seeds in `cup` to `seeds` of what's there, keeping the hash up
+    // to date, and returns what was there.
+    fn change<F>(&mut self, cup: CupPos, seeds: F) -> Option<usize>
+    where
+        F: FnOnce(usize) -> Option<usize>,
+    {
+        let index = self.index(cup)?;
+        let before = self.seeds[index];
+        let after = seeds(before)?;
+        self.seeds[index] = after;
+        self.hash ^= zobrist::cup(index, before) ^ zobrist::cup(index, after);
+        Some(before)
+    }
+
+    pub(crate) fn deposit(&mut self, player: Player, seeds: usize) {
+        let before = self.bank.get(player);
+        let after = self.bank.deposit(player, seeds);
+        self.hash ^= zobrist::bank(player, before) ^ zobrist::bank(player, after);
+    }
+
+    fn withdraw(&mut self, player: Player, seeds: usize) -> Option<()> {
+        let before = self.bank.get(player);
+        let after = self.bank.withdraw(player, seeds)?;
+        self.hash ^= zobrist::bank(player, before) ^ zobrist::bank(player, after);
+        Some(())
+    }
+
+    /// A Zobrist key for the seeds in every cup and bank, kept up to date as
+    /// they move. Boards with the same seeds in the same places have the same
+    /// key whatever their history. `Game::zobrist` adds the side to move.
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    // Works the key out from scratch.
+    #[cfg(any(test, feature = "serde"))]
+    fn rehash(&self) -> u64 {
+        let cups = self
+            .seeds
+            .iter()
+            .enumerate()
+            .fold(0, |hash, (index, seeds)| hash ^ zobrist::cup(index, *seeds));
+        [Player::Player1, Player::Player2]
+            .iter()
+            .fold(cups, |hash, player| {
+                hash ^ zobrist::bank(*player, self.bank.get(*player))
             })
     }
 
+    // The seeds in each of `player`'s cups, in sowing order.
+    fn row(&self, player: Player) -> &[usize] {
+        let per_player = self.topology.cups_per_player();
+        match player {
+            Player::Player1 => &self.seeds[..per_player],
+            Player::Player2 => &self.seeds[per_player..2 * per_player],
+        }
+    }
+
+    pub fn get_bank(&self, player: Player) -> usize {
+        self.bank.get(player)
+    }
+
+    /// Seeds `player` has picked up and not yet sown.
+    pub fn in_hand(&self, player: Player) -> usize {
+        self.in_hand.get(player)
+    }
+
+    pub fn seeds(&self, pit: Pit) -> usize {
+        match pit {
+            Pit::Cup(cup) => self.index(cup).map_or(0, |index| self.seeds[index]),
+            Pit::Store(player) => self.bank.get(player),
+        }
+    }
+
+    pub fn get_cup(&self, cup: CupPos) -> Option<Cup> {
+        self.index(cup).map(|index| Cup {
+            owner: cup.owner,
+            seeds: self.seeds[index],
+            pos: cup.pos,
+        })
+    }
+
+    pub fn starving(&self, player: Player) -> bool {
+        self.row(player).iter().all(|seeds| *seeds == 0)
+    }
+
+    pub fn pickup(&mut self, cup: CupPos, player: Player) -> Option<()> {
+        let seeds = self.change(cup, |_| Some(0))?;
+        self.in_hand.take(player, seeds);
+        if seeds > 0 {
+            self.houses.retain(|house| *house != cup);
+            self.log(Move::Pickup(cup));
+        }
+        Some(())
+    }
+
+    /// Picks up `seeds` of the seeds in `cup`, leaving the rest behind.
+    pub fn take(&mut self, cup: CupPos, seeds: usize, player: Player) -> Result<()> {
+        self.index(cup).ok_or(MancalaError::NoSuchCup)?;
+        self.change(cup, |left| left.checked_sub(seeds))
+            .ok_or(MancalaError::NoSeedsToSow)?;
+        self.in_hand.take(player, seeds);
+        if seeds > 0 {
+            self.log(Move::Take(cup, seeds));
+        }
+        Ok(())
+    }
+
+    /// Takes `seeds` out of `player`'s store and into their hand.
+    pub fn draw(&mut self, player: Player, seeds: usize) -> Result<()> {
+        self.withdraw(player, seeds)
+            .ok_or(MancalaError::NoSeedsToSow)?;
+        self.in_hand.take(player, seeds);
+        if seeds > 0 {
+            self.log(Move::Draw(player, seeds));
+        }
+        Ok(())
+    }
+
     // Move this into board, take a filter argument to validate that this is a cell you should be able to sow into
-    pub(crate) fn sow<F>(&mut self, player: Player, cup: CupPos, filter: F) -> Result<Cup>
+    // Sowing starts in the pit after `cup`, following the loop `cup` is on.
+    // Stores in the loop only take seeds from their owner.
+    pub fn sow<F>(&mut self, player: Player, cup: CupPos, filter: F) -> Result<Pit>
     where
         F: Fn(&CupPos, Player, usize) -> bool,
     {
-        let seeds = self.in_hand.get(player);
-        let final_cup = self
-            .cups
-            .clone()
-            .iter()
-            .map(CupPos::from)
-            .cycle()
-            .skip_while(|c| *c != cup)
-            .filter(|c| filter(c, cup.owner, cup.pos))
-            .take(seeds)
-            .map(|cup_pos| {
-                let mut cup = self.get_mut_cup(cup_pos).expect("");
-                cup.seeds += 1;
-                let clone = cup.clone();
-                if let Some(moves) = self.moves.last_mut() {
-                    moves.push(Move::Place(clone.clone().into()))
+        self.sow_towards(player, cup, Direction::Clockwise, filter)
+    }
+
+    /// Like `sow`, but lets the caller pick which way round the loop to go.
+    pub fn sow_towards<F>(
+        &mut self,
+        player: Player,
+        cup: CupPos,
+        direction: Direction,
+        filter: F,
+    ) -> Result<Pit>
+    where
+        F: Fn(&CupPos, Player, usize) -> bool,
+    {
+        self.sow_loop(player, cup, direction, 1, filter)
+    }
+
+    /// Sows the seeds in hand starting with `cup` itself rather than the pit after it.
+    pub fn sow_into(&mut self, player: Player, cup: CupPos, direction: Direction) -> Result<Pit> {
+        self.sow_loop(player, cup, direction, 0, |_, _, _| true)
+    }
+
+    fn sow_loop<F>(
+        &mut self,
+        player: Player,
+        cup: CupPos,
+        direction: Direction,
+        skip: usize,
+        filter: F,
+    ) -> Result<Pit>
+    where
+        F: Fn(&CupPos, Player, usize) -> bool,
+    {
+        let topology = self.topology;
+        let length = topology.loop_len();
+        if length == 0 {
+            return Err(MancalaError::IllegalMove);
+        }
+        let step = match direction {
+            Direction::Clockwise => 1,
+            Direction::Anticlockwise => length - 1,
+        };
+        let first = topology
+            .loop_index(Pit::Cup(cup))
+            .ok_or(MancalaError::NoSuchCup)?
+            + skip * step;
+        let pit_at =
+            |lap_index: usize| topology.loop_pit(cup.owner, (first + lap_index * step) % length);
+        let sown = |pit: &Pit| match pit {
+            Pit::Cup(c) => filter(c, cup.owner, cup.pos),
+            Pit::Store(owner) => *owner == player,
+        };
+
+        let seeds = self.in_hand.drop(player);
+        let open = (0..length).map(pit_at).filter(sown).count();
+        if seeds == 0 {
+            return Err(MancalaError::NoSeedsToSow);
+        } else if open == 0 {
+            self.in_hand.take(player, seeds);
+            return Err(MancalaError::IllegalMove);
+        }
+        // Every open pit gets a seed a lap, and the first `rest` get one more.
+        let (laps, rest) = (seeds / open, seeds % open);
+        let last = if rest > 0 { rest - 1 } else { open - 1 };
+        let mut final_pit = None;
+        for (reached, pit) in (0..length).map(pit_at).filter(sown).enumerate() {
+            let count = laps + (reached < rest) as usize;
+            if count > 0 {
+                let placed = self.placing(pit, count);
+                self.apply(&placed)
+                    .expect("pits in the sowing loop are on the board");
+                if !self.seed_log {
+                    self.log(placed);
                 }
-                clone
-            })
-            .last();
-        self.in_hand.drop(player);
+            }
+            if reached == last {
+                final_pit = Some(pit);
+            }
+        }
+        if self.seed_log {
+            for pit in (0..).map(pit_at).filter(sown).take(seeds) {
+                let placed = self.placing(pit, 1);
+                self.log(placed);
+            }
+        }
 
-        final_cup.ok_or(MancalaError::NoSeedsToSow)
+        final_pit.ok_or(MancalaError::NoSeedsToSow)
     }
 
-    pub(crate) fn bank(&mut self, player: Player) {
+    // What dropping `seeds` seeds into `pit` logs as. A tuzdyk passes its
+    // seeds on to the store it feeds.
+    fn placing(&self, pit: Pit, seeds: usize) -> Move {
+        match pit {
+            Pit::Cup(cup) => match self.tuzdyk(cup) {
+                Some(owner) => Move::Bank(owner, seeds),
+                None if seeds == 1 => Move::Place(cup),
+                None => Move::Sow(cup, seeds),
+            },
+            Pit::Store(owner) => Move::Bank(owner, seeds),
+        }
+    }
+
+    pub fn bank(&mut self, player: Player) {
         let value = self.in_hand.drop(player);
-        self.bank.deposit(player, value);
+        self.deposit(player, value);
         if value > 0 {
-            if let Some(moves) = self.moves.last_mut() {
-                moves.push(Move::Bank(player, value));
-            }
+            self.log(Move::Bank(player, value));
         }
     }
 }
 
+/// Iterator over the boards a turn passes through, see `MancalaBoard::replay`.
+///
+/// The log doesn't say whose hand seeds pass through, so every board shows
+/// them in the hand of the player who started the turn, the owner of the
+/// first cup picked up or store drawn from.
+pub struct Replay<'a> {
+    board: MancalaBoard,
+    turn: std::slice::Iter<'a, Move>,
+    hand: Player,
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = MancalaBoard;
+
+    fn next(&mut self) -> Option<MancalaBoard> {
+        let played = self.turn.next()?;
+        let (picked, dropped) = match *played {
+            Move::Pickup(cup) => (self.board.get_cup(cup)?.seeds, 0),
+            Move::Take(_, seeds) | Move::Draw(_, seeds) => (seeds, 0),
+            Move::Place(_) => (0, 1),
+            Move::Sow(_, seeds) | Move::Bank(_, seeds) => (0, seeds),
+            Move::House(_) | Move::Tuzdyk(_, _) => (0, 0),
+        };
+        self.board.apply(played)?;
+        let held = (self.board.in_hand.drop(self.hand) + picked).checked_sub(dropped)?;
+        self.board.in_hand.take(self.hand, held);
+        Some(self.board.clone())
+    }
+}
+
+fn glyph(seeds: usize) -> String {
+    VALUES
+        .get(seeds)
+        .map(char::to_string)
+        .unwrap_or_else(|| format!("({})", seeds))
+}
+
 const VALUES: [char; 21] = [
     '\u{24EA}', '\u{2460}', '\u{2461}', '\u{2462}', '\u{2463}', '\u{2464}', '\u{2465}', '\u{2466}',
     '\u{2467}', '\u{2468}', '\u{2469}', '\u{2470}', '\u{2471}', '\u{2472}', '\u{2473}', '\u{2474}',
@@ -265,18 +863,46 @@ const VALUES: [char; 21] = [
 
 impl fmt::Display for MancalaBoard {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (top, bottom) = self.cups.split_at(self.cups.len() / 2);
-
-        let top: String = top.iter().map(|x| VALUES[x.seeds]).join("|");
-        let bottom: String = bottom.iter().map(|x| VALUES[x.seeds]).join("|");
-        write!(
-            fmt,
-            "{} - {}\n{} - {}",
-            self.bank.get(Player::Player1),
-            top,
-            bottom,
-            self.bank.get(Player::Player2)
-        )
+        match self.topology {
+            Topology::TwoRow { .. } => {
+                let top: String = self
+                    .row(Player::Player1)
+                    .iter()
+                    .map(|x| glyph(*x))
+                    .join("|");
+                let bottom: String = self
+                    .row(Player::Player2)
+                    .iter()
+                    .map(|x| glyph(*x))
+                    .join("|");
+                write!(
+                    fmt,
+                    "{} - {}\n{} - {}",
+                    self.bank.get(Player::Player1),
+                    top,
+                    bottom,
+                    self.bank.get(Player::Player2)
+                )
+            }
+            Topology::FourRow { pits } => {
+                let row = |owner: Player, positions: &mut dyn Iterator<Item = usize>| {
+                    positions
+                        .map(|pos| glyph(self.seeds(Pit::Cup(CupPos { owner, pos }))))
+                        .join("|")
+                };
+                // Drawn as seen from above, so cups that face each other line up.
+                write!(
+                    fmt,
+                    "{} - {}\n{}\n{}\n{} - {}",
+                    self.bank.get(Player::Player1),
+                    row(Player::Player1, &mut (pits..2 * pits).rev()),
+                    row(Player::Player1, &mut (0..pits)),
+                    row(Player::Player2, &mut (0..pits).rev()),
+                    row(Player::Player2, &mut (pits..2 * pits)),
+                    self.bank.get(Player::Player2)
+                )
+            }
+        }
     }
 }
 
@@ -295,7 +921,7 @@ mod tests {
                 })
             }
         }
-        let mut board = MancalaBoard::new(board);
+        let mut board = MancalaBoard::new(board).unwrap();
         board.new_move();
         board
     }
@@ -307,6 +933,22 @@ mod tests {
         assert_eq!(format!("{}", build_board(2, 2)), "0 - ②\n② - 0");
     }
 
+    #[test]
+    fn checks_cups() {
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            MancalaBoard::new(Cup::rows(&[1; 17], &[1; 17]))
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            MancalaBoard::with_topology(
+                Cup::rows(&[1, 2, 3], &[3, 4, 5]),
+                Topology::FourRow { pits: 1 }
+            )
+        );
+        assert!(MancalaBoard::new(Cup::rows(&[1; 16], &[1; 16])).is_ok());
+    }
+
     #[test]
     fn remove() {
         let mut board = build_board(12, 4);
@@ -355,11 +997,13 @@ mod tests {
         );
         assert_eq!(0, board.in_hand.get(Player::Player1));
         assert_eq!("0 - ⓪|②\n③|③ - 0", format!("{}", board));
+        let cup = cup.unwrap();
         assert_eq!(
-            "Cup { owner: Player2, seeds: 3, pos: 1 }",
-            format!("{:?}", cup.unwrap())
+            "Cup(CupPos { owner: Player2, pos: 1 })",
+            format!("{:?}", cup)
         );
-        assert_eq!("[[Pickup(CupPos { owner: Player1, pos: 0 }), Place(CupPos { owner: Player2, pos: 0 }), Place(CupPos { owner: Player2, pos: 1 })]]", format!("{:?}", board.moves))
+        assert_eq!(3, board.seeds(cup));
+        assert_eq!("[Pickup(CupPos { owner: Player1, pos: 0 }), Place(CupPos { owner: Player2, pos: 0 }), Place(CupPos { owner: Player2, pos: 1 })]", format!("{:?}", board.moves.last().unwrap()))
     }
 
     #[test]
@@ -395,7 +1039,8 @@ mod tests {
                 owner: Player::Player2,
                 pos: 2,
             },
-        ]);
+        ])
+        .unwrap();
         board.pickup(
             CupPos {
                 pos: 0,
@@ -413,9 +1058,280 @@ mod tests {
         );
         assert_eq!(0, board.in_hand.get(Player::Player1));
         assert_eq!("0 - ⓪|①|①\n⓪|⓪|⓪ - 0", format!("{}", board));
+        let cup = cup.unwrap();
         assert_eq!(
-            "Cup { owner: Player1, seeds: 1, pos: 1 }",
-            format!("{:?}", cup.unwrap())
-        )
+            "Cup(CupPos { owner: Player1, pos: 1 })",
+            format!("{:?}", cup)
+        );
+        assert_eq!(1, board.seeds(cup));
+    }
+
+    #[test]
+    fn zobrist() {
+        let mut board = build_board(12, 4);
+        let start = board.zobrist();
+        assert_eq!(board.rehash(), start);
+        let cup = CupPos {
+            owner: Player::Player1,
+            pos: 2,
+        };
+        board.pickup(cup, Player::Player1);
+        board.sow(Player::Player1, cup, skip_origin).unwrap();
+        let next = CupPos {
+            owner: Player::Player1,
+            pos: 3,
+        };
+        board.take(next, 1, Player::Player1).unwrap();
+        board.bank(Player::Player1);
+        assert_ne!(start, board.zobrist());
+        assert_eq!(board.rehash(), board.zobrist());
+
+        board.undo_move();
+        assert_eq!(start, board.zobrist());
+    }
+
+    #[test]
+    fn sow_many_laps() {
+        let start = CupPos {
+            owner: Player::Player1,
+            pos: 0,
+        };
+        let sow = |seed_log: bool| {
+            let mut board = build_board(4, 0);
+            board.log_each_seed(seed_log);
+            board.in_hand.take(Player::Player1, 7);
+            let pit = board.sow(Player::Player1, start, skip_origin);
+            (board, pit)
+        };
+        let (board, pit) = sow(false);
+        assert_eq!(
+            Ok(Pit::Cup(CupPos {
+                owner: Player::Player1,
+                pos: 1,
+            })),
+            pit
+        );
+        assert_eq!("0 - ⓪|③\n②|② - 0", format!("{}", board));
+        assert_eq!(
+            &vec![
+                Move::Sow(
+                    CupPos {
+                        owner: Player::Player1,
+                        pos: 1
+                    },
+                    3
+                ),
+                Move::Sow(
+                    CupPos {
+                        owner: Player::Player2,
+                        pos: 0
+                    },
+                    2
+                ),
+                Move::Sow(
+                    CupPos {
+                        owner: Player::Player2,
+                        pos: 1
+                    },
+                    2
+                ),
+            ],
+            board.moves.last().unwrap()
+        );
+
+        let (detailed, pit) = sow(true);
+        assert_eq!(
+            Ok(Pit::Cup(CupPos {
+                owner: Player::Player1,
+                pos: 1
+            })),
+            pit
+        );
+        assert_eq!(board.cups(), detailed.cups());
+        assert_eq!(7, detailed.moves.last().unwrap().len());
+    }
+
+    #[test]
+    fn sow_through_stores() {
+        let mut cups = Vec::new();
+        for owner in [Player::Player1, Player::Player2].iter() {
+            for i in 0..2 {
+                cups.push(Cup {
+                    owner: *owner,
+                    seeds: 3,
+                    pos: i,
+                })
+            }
+        }
+        let mut board = MancalaBoard::with_stores(cups).unwrap();
+        board.new_move();
+        let start = CupPos {
+            pos: 1,
+            owner: Player::Player1,
+        };
+        board.pickup(start, Player::Player1);
+        let pit = board.sow(Player::Player1, start, |_, _, _| true);
+        assert_eq!(
+            Ok(Pit::Cup(CupPos {
+                pos: 1,
+                owner: Player::Player2,
+            })),
+            pit
+        );
+        assert_eq!("1 - ③|⓪\n④|④ - 0", format!("{}", board));
+
+        let start = CupPos {
+            pos: 1,
+            owner: Player::Player2,
+        };
+        board.new_move();
+        board.pickup(start, Player::Player2);
+        let pit = board.sow(Player::Player2, start, |_, _, _| true);
+        assert_eq!(
+            Ok(Pit::Cup(CupPos {
+                pos: 0,
+                owner: Player::Player2,
+            })),
+            pit
+        );
+        assert_eq!("1 - ④|①\n⑤|⓪ - 1", format!("{}", board));
+        assert_eq!("[Pickup(CupPos { owner: Player2, pos: 1 }), Bank(Player2, 1), Place(CupPos { owner: Player1, pos: 0 }), Place(CupPos { owner: Player1, pos: 1 }), Place(CupPos { owner: Player2, pos: 0 })]", format!("{:?}", board.moves.last().unwrap()))
+    }
+
+    #[test]
+    fn sow_past_tuzdyk() {
+        let mut board = build_board(6, 1);
+        let tuzdyk = CupPos {
+            pos: 0,
+            owner: Player::Player2,
+        };
+        board.pickup(tuzdyk, Player::Player1);
+        board.bank(Player::Player1);
+        board.add_tuzdyk(tuzdyk, Player::Player1);
+        assert_eq!(Some(Player::Player1), board.tuzdyk(tuzdyk));
+        board.new_move();
+        let start = CupPos {
+            pos: 2,
+            owner: Player::Player2,
+        };
+        board.pickup(start, Player::Player2);
+        board.in_hand.take(Player::Player2, 3);
+        let pit = board.sow(Player::Player2, start, |_, _, _| true);
+        assert_eq!(Ok(Pit::Cup(tuzdyk)), pit);
+        assert_eq!("2 - ②|②|②\n⓪|①|⓪ - 0", format!("{}", board));
+        assert_eq!("[Pickup(CupPos { owner: Player2, pos: 2 }), Place(CupPos { owner: Player1, pos: 0 }), Place(CupPos { owner: Player1, pos: 1 }), Place(CupPos { owner: Player1, pos: 2 }), Bank(Player1, 1)]", format!("{:?}", board.moves.last().unwrap()))
+    }
+
+    #[test]
+    fn replay_turn() {
+        let mut board = build_board(4, 2);
+        let start = CupPos {
+            pos: 1,
+            owner: Player::Player1,
+        };
+        board.pickup(start, Player::Player1);
+        let last = board.sow(Player::Player1, start, |_, _, _| true);
+        if let Ok(Pit::Cup(last)) = last {
+            board.pickup(last, Player::Player1);
+            board.bank(Player::Player1);
+        }
+        assert_eq!("3 - ②|⓪\n③|⓪ - 0", format!("{}", board));
+
+        let frames: Vec<MancalaBoard> = board.replay_last_turn().unwrap().collect();
+        assert_eq!(
+            vec![2, 1, 0, 3, 0],
+            frames
+                .iter()
+                .map(|frame| frame.in_hand(Player::Player1))
+                .collect::<Vec<_>>()
+        );
+        assert!(frames.iter().all(|frame| frame.total() == board.total()));
+        let steps: Vec<String> = frames.iter().map(|board| format!("{}", board)).collect();
+        assert_eq!(
+            vec![
+                "0 - ②|⓪\n②|② - 0",
+                "0 - ②|⓪\n③|② - 0",
+                "0 - ②|⓪\n③|③ - 0",
+                "0 - ②|⓪\n③|⓪ - 0",
+                "3 - ②|⓪\n③|⓪ - 0",
+            ],
+            steps
+        );
+        assert!(MancalaBoard::new(Vec::new())
+            .unwrap()
+            .replay_last_turn()
+            .is_none());
+    }
+
+    #[test]
+    fn sow_empty_loop() {
+        let mut board = MancalaBoard::new(Vec::new()).unwrap();
+        let cup = CupPos {
+            owner: Player::Player1,
+            pos: 0,
+        };
+        for direction in [Direction::Clockwise, Direction::Anticlockwise].iter() {
+            assert_eq!(
+                Err(MancalaError::IllegalMove),
+                board.sow_towards(Player::Player1, cup, *direction, |_, _, _| true)
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_checks_log() {
+        let mut board = build_board(4, 2);
+        let start = CupPos {
+            pos: 1,
+            owner: Player::Player1,
+        };
+        board.pickup(start, Player::Player1);
+        board.sow(Player::Player1, start, |_, _, _| true).unwrap();
+        let json = serde_json::to_value(&board).unwrap();
+        assert_eq!(board, serde_json::from_value(json.clone()).unwrap());
+
+        let tampered = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            change(&mut json);
+            serde_json::from_value::<MancalaBoard>(json).is_err()
+        };
+        assert!(tampered(&|json| json["cups"][0]["seeds"] = 5.into()));
+        assert!(tampered(&|json| json["cups"][0]["owner"] = "Player2".into()));
+        assert!(tampered(&|json| json["bank"]["player1"] = 1.into()));
+        assert!(tampered(&|json| {
+            json["moves"][1].as_array_mut().unwrap().pop();
+        }));
+    }
+
+    #[test]
+    fn sow_four_rows() {
+        let mut cups = Vec::new();
+        for owner in [Player::Player1, Player::Player2].iter() {
+            for i in 0..4 {
+                cups.push(Cup {
+                    owner: *owner,
+                    seeds: i,
+                    pos: i,
+                })
+            }
+        }
+        let mut board = MancalaBoard::with_topology(cups, Topology::FourRow { pits: 2 }).unwrap();
+        board.new_move();
+        assert_eq!("0 - ③|②\n⓪|①\n①|⓪\n②|③ - 0", format!("{}", board));
+        let start = CupPos {
+            pos: 3,
+            owner: Player::Player1,
+        };
+        board.pickup(start, Player::Player1);
+        let pit = board.sow(Player::Player1, start, |_, _, _| true);
+        assert_eq!(
+            Ok(Pit::Cup(CupPos {
+                pos: 2,
+                owner: Player::Player1,
+            })),
+            pit
+        );
+        assert_eq!("0 - ⓪|③\n①|②\n①|⓪\n②|③ - 0", format!("{}", board));
     }
 }
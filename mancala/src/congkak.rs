@@ -0,0 +1,221 @@
+//! Congkak, also played as Sungka: seven houses a side with a store after
+//! each row.
+//!
+//! Sowing relays: a seed that lands in an occupied house picks the house up
+//! and keeps going, in either row. Sowing stops in the player's own store,
+//! which earns another turn, or in an empty house. Stopping in an empty house
+//! of your own takes it and the house opposite. Once either row is empty each
+//! player stores whatever is left on their side.
+//!
+//! Both players can open at the same time. The two sowings are interleaved a
+//! lap at a time, Player 1 first: each lap sows everything in hand, and a lap
+//! that ends in an occupied house picks it up ready for that player's next
+//! lap. A player's opening stops where their sowing would stop on a normal
+//! turn, including their store, and any capture is made straight away. The
+//! player who stops first, Player 1 on a tie, moves first once play starts
+//! alternating.
+
+use crate::board::{Cup, CupPos, MancalaBoard, Pit};
+use crate::game::{capture_opposite, store_turn, sweep_sides, Ruleset, MAX_RELAYS};
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const PITS: usize = 7;
+const STARTING_COUNT: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Congkak {
+    simultaneous_start: bool,
+}
+
+impl Default for Congkak {
+    fn default() -> Self {
+        Congkak::new(true)
+    }
+}
+
+impl Congkak {
+    pub fn new(simultaneous_start: bool) -> Congkak {
+        Congkak { simultaneous_start }
+    }
+
+    // Picks up the house a lap ended in, if the sowing carries on from it.
+    fn relay(&self, board: &mut MancalaBoard, player: Player, last: Pit) -> Option<CupPos> {
+        match last {
+            Pit::Cup(cup) if board.seeds(last) > 1 => {
+                board.pickup(cup, player);
+                Some(cup)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Ruleset for Congkak {
+    type Move = usize;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..PITS).map(move |i| Cup {
+                    owner: *player,
+                    seeds: STARTING_COUNT,
+                    pos: i,
+                })
+            })
+            .collect();
+
+        MancalaBoard::with_stores(board).expect("a Congkak board fits")
+    }
+
+    fn simultaneous_start(&self) -> bool {
+        self.simultaneous_start
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, cup: usize) -> Result<()> {
+        match board.get_cup(CupPos {
+            owner: player,
+            pos: cup,
+        }) {
+            Some(Cup { seeds: 0, .. }) => Err(MancalaError::NoSeedsToSow),
+            None => Err(MancalaError::NoSuchCup),
+            _ => Ok(()),
+        }
+    }
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<usize> {
+        (0..PITS)
+            .filter(|cup| self.validate(board, player, *cup).is_ok())
+            .collect()
+    }
+
+    fn sow(&self, board: &mut MancalaBoard, player: Player, cup: usize) -> Result<Pit> {
+        let mut cup = CupPos {
+            owner: player,
+            pos: cup,
+        };
+        board.pickup(cup, player);
+        for _ in 0..=MAX_RELAYS {
+            let last = board.sow(player, cup, |_, _, _| true)?;
+            match self.relay(board, player, last) {
+                Some(next) => cup = next,
+                None => return Ok(last),
+            }
+        }
+        Err(MancalaError::IllegalMove)
+    }
+
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit) {
+        capture_opposite(board, player, last)
+    }
+
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, last: Pit) -> GameState {
+        store_turn(board, player, last)
+    }
+
+    fn sow_simultaneous(
+        &self,
+        board: &mut MancalaBoard,
+        player1: usize,
+        player2: usize,
+    ) -> Result<GameState> {
+        let mut sowing: Vec<(Player, Option<CupPos>)> =
+            [(Player::Player1, player1), (Player::Player2, player2)]
+                .iter()
+                .map(|(player, pos)| {
+                    let cup = CupPos {
+                        owner: *player,
+                        pos: *pos,
+                    };
+                    board.pickup(cup, *player);
+                    (*player, Some(cup))
+                })
+                .collect();
+
+        let mut first = None;
+        let mut laps = 0;
+        while sowing.iter().any(|(_, cup)| cup.is_some()) {
+            laps += 1;
+            if laps > MAX_RELAYS {
+                return Err(MancalaError::IllegalMove);
+            }
+            for (player, from) in sowing.iter_mut() {
+                if let Some(cup) = *from {
+                    let last = board.sow(*player, cup, |_, _, _| true)?;
+                    *from = self.relay(board, *player, last);
+                    if from.is_none() {
+                        self.capture(board, *player, last);
+                        first.get_or_insert(*player);
+                    }
+                }
+            }
+        }
+
+        Ok(sweep_sides(board).unwrap_or(GameState::InProgress(first.unwrap_or(Player::Player1))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Move;
+    use crate::game::Game;
+
+    #[test]
+    fn relay_sowing() -> Result<()> {
+        let mut game = Game::new(Congkak::new(false));
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        game.play(0)?;
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        game.play(6)?;
+        assert_eq!("3 - ①|⑨|⑨|⑨|⑨|⑨|①\n⑧|⑧|⑧|⑧|⑧|⑧|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        Ok(())
+    }
+
+    #[test]
+    fn simultaneous_start() -> Result<()> {
+        let mut game = Game::new(Congkak::default());
+        assert_eq!(game.state, GameState::Simultaneous);
+        assert_eq!(Err(MancalaError::IllegalMove), game.play(0));
+
+        // Player 1 relays out of Player 2's first house after Player 2 has
+        // already stopped in their store.
+        let mut game = Game::with_board(
+            Congkak::default(),
+            MancalaBoard::with_stores(Cup::rows(&[1, 0, 0, 0, 0, 0, 2], &[1, 0, 0, 0, 0, 0, 1]))?,
+            GameState::Simultaneous,
+        );
+        game.play_simultaneous(6, 6)?;
+        assert_eq!("1 - ①|⓪|⓪|⓪|⓪|⓪|⓪\n⓪|①|①|⓪|⓪|⓪|⓪ - 1", format!("{}", game));
+        let player1 = |pos| CupPos {
+            owner: Player::Player1,
+            pos,
+        };
+        let player2 = |pos| CupPos {
+            owner: Player::Player2,
+            pos,
+        };
+        assert_eq!(
+            &vec![
+                Move::Pickup(player1(6)),
+                Move::Pickup(player2(6)),
+                Move::Bank(Player::Player1, 1),
+                Move::Place(player2(0)),
+                Move::Pickup(player2(0)),
+                Move::Bank(Player::Player2, 1),
+                Move::Place(player2(1)),
+                Move::Place(player2(2)),
+            ],
+            game.board().moves.last().unwrap()
+        );
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        assert_eq!(Err(MancalaError::IllegalMove), game.play_simultaneous(1, 1));
+        game.play(1)?;
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Ways of scoring an Ayoayo position for `ai::search_with`, so heuristics
+//! can be tried out without changing the search.
+//!
+//! Each built-in term scores the player against their opponent, so a
+//! position scores the same for one player as it does negated for the
+//! other, the way negamax expects. A position without the cups `rules`
+//! sets up scores 0.
+
+use crate::ayoayo::Ayoayo;
+use crate::position::Position;
+use crate::Player;
+
+pub trait Evaluator {
+    /// Scores `position` from `player`'s point of view, higher being better.
+    /// Scores should stay well below `ai::WIN`.
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32;
+}
+
+impl<F: Fn(&Ayoayo, &Position, Player) -> i32> Evaluator for F {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        self(rules, position, player)
+    }
+}
+
+/// Seeds in the player's bank less the opponent's.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BankDifference;
+
+impl Evaluator for BankDifference {
+    fn evaluate(&self, _rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        position.bank(player) as i32 - position.bank(player.next_player()) as i32
+    }
+}
+
+/// Seeds in the player's cups less the opponent's.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SeedsOnSide;
+
+impl Evaluator for SeedsOnSide {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        let seeds = |player| -> i32 {
+            rules
+                .side(position, player)
+                .unwrap_or(&[])
+                .iter()
+                .map(|seeds| *seeds as i32)
+                .sum()
+        };
+        seeds(player) - seeds(player.next_player())
+    }
+}
+
+/// Cups the player could play less the cups the opponent could, as if it
+/// were each one's turn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Mobility;
+
+impl Evaluator for Mobility {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        let moves = |to_move| {
+            let mut position = *position;
+            position.to_move = to_move;
+            rules.legal_cups(&position).count() as i32
+        };
+        moves(player) - moves(player.next_player())
+    }
+}
+
+/// Seeds the opponent has in cups facing an empty cup less the player's.
+/// A sowing that ends in that empty cup captures them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vulnerable;
+
+impl Evaluator for Vulnerable {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        let exposed = |player: Player| -> i32 {
+            let opposite = rules.side(position, player.next_player()).unwrap_or(&[]);
+            rules
+                .side(position, player)
+                .unwrap_or(&[])
+                .iter()
+                .zip(opposite.iter())
+                .filter(|(_, opposite)| **opposite == 0)
+                .map(|(seeds, _)| *seeds as i32)
+                .sum()
+        };
+        exposed(player.next_player()) - exposed(player)
+    }
+}
+
+/// 1 when the opponent has no seeds left, and -1 when the player hasn't.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Starving;
+
+impl Evaluator for Starving {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        let starving = |player| rules.starving(position, player).unwrap_or(false) as i32;
+        starving(player.next_player()) - starving(player)
+    }
+}
+
+/// The sum of other evaluators' scores, each multiplied by its weight.
+#[derive(Default)]
+pub struct Weighted {
+    terms: Vec<(i32, Box<dyn Evaluator>)>,
+}
+
+impl Weighted {
+    pub fn new() -> Weighted {
+        Weighted::default()
+    }
+
+    pub fn with(mut self, weight: i32, evaluator: impl Evaluator + 'static) -> Weighted {
+        self.terms.push((weight, Box::new(evaluator)));
+        self
+    }
+}
+
+impl Evaluator for Weighted {
+    fn evaluate(&self, rules: &Ayoayo, position: &Position, player: Player) -> i32 {
+        self.terms
+            .iter()
+            .map(|(weight, evaluator)| weight * evaluator.evaluate(rules, position, player))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::AyoayoConfig;
+    use crate::Result;
+
+    fn three_pits() -> Ayoayo {
+        Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn terms() -> Result<()> {
+        let rules = three_pits();
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        let scores = |position: &Position, player| {
+            let evaluators: [&dyn Evaluator; 5] = [
+                &BankDifference,
+                &SeedsOnSide,
+                &Mobility,
+                &Vulnerable,
+                &Starving,
+            ];
+            evaluators
+                .iter()
+                .map(|evaluator| evaluator.evaluate(&rules, position, player))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, -1, 1, 1, 0], scores(&position, Player::Player1));
+        assert_eq!(vec![-1, 1, -1, -1, 0], scores(&position, Player::Player2));
+
+        let starved: Position = "1,0,1/0,0,0 8/10 0/0 1".parse()?;
+        assert_eq!(1, Starving.evaluate(&rules, &starved, Player::Player1));
+        assert_eq!(-1, Starving.evaluate(&rules, &starved, Player::Player2));
+
+        // Six cups a side is the wrong board for these rules.
+        let standard: Position = "4,4,4,4,4,4/4,4,4,4,4,0 0/0 0/0 1".parse()?;
+        assert_eq!(vec![0, 0, 0, 0, 0], scores(&standard, Player::Player1));
+        Ok(())
+    }
+
+    #[test]
+    fn weighted() -> Result<()> {
+        let rules = three_pits();
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        let evaluator = Weighted::new()
+            .with(3, BankDifference)
+            .with(2, SeedsOnSide)
+            .with(1, |_: &Ayoayo, _: &Position, _| 10);
+        assert_eq!(
+            3 - 2 + 10,
+            evaluator.evaluate(&rules, &position, Player::Player1)
+        );
+        assert_eq!(
+            0,
+            Weighted::new().evaluate(&rules, &position, Player::Player1)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+use crate::board::{Cup, CupPos, MancalaBoard, Pit};
+use crate::position::Position;
+use crate::zobrist;
+use crate::{GameState, MancalaError, Player, Result};
+use compare::{natural, Compare};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Relay sowing can go round forever on some boards, so a turn that is
+/// still relaying after this many laps is turned down as an `IllegalMove`.
+pub(crate) const MAX_RELAYS: usize = 10_000;
+
+/// The rules of a mancala variant, built on top of a `MancalaBoard`.
+///
+/// `Game` drives a turn by calling `validate`, `sow`, `capture` and
+/// `end_of_turn` in that order, so a variant only has to describe what is
+/// different about it.
+pub trait Ruleset {
+    /// What a player picks on their turn, usually the index of one of their cups.
+    type Move: Copy + fmt::Debug + PartialEq;
+
+    /// The board as it is laid out before the first move.
+    fn setup(&self) -> MancalaBoard;
+
+    fn first_player(&self) -> Player {
+        Player::Player1
+    }
+
+    /// Whether the game opens with both players moving at the same time.
+    fn simultaneous_start(&self) -> bool {
+        false
+    }
+
+    /// Checks that `player` is allowed to make `play` without changing the board.
+    fn validate(&self, board: &MancalaBoard, player: Player, play: Self::Move) -> Result<()>;
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<Self::Move>;
+
+    /// Sows the seeds picked up by `play`, returning the pit the last seed landed in.
+    fn sow(&self, board: &mut MancalaBoard, player: Player, play: Self::Move) -> Result<Pit>;
+
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit);
+
+    /// Decides who moves next, or finishes the game.
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, last: Pit) -> GameState;
+
+    /// Plays both players' opening moves together, including any captures,
+    /// and decides who moves next.
+    fn sow_simultaneous(
+        &self,
+        _board: &mut MancalaBoard,
+        _player1: Self::Move,
+        _player2: Self::Move,
+    ) -> Result<GameState> {
+        Err(MancalaError::IllegalMove)
+    }
+}
+
+/// Plays `play` on a scratch copy of the board and reports whether the
+/// opponent is left without seeds. A move that can't be sown feeds no one.
+pub fn leaves_starving<R: Ruleset>(
+    rules: &R,
+    board: &MancalaBoard,
+    player: Player,
+    play: R::Move,
+) -> bool {
+    let mut board = board.clone();
+    match rules.sow(&mut board, player, play) {
+        Ok(last) => {
+            rules.capture(&mut board, player, last);
+            board.starving(player.next_player())
+        }
+        Err(_) => true,
+    }
+}
+
+/// The must-feed rule: if the opponent has no seeds, `play` has to give them
+/// some unless none of the `alternatives` could either.
+pub fn must_feed<R, I>(
+    rules: &R,
+    board: &MancalaBoard,
+    player: Player,
+    play: R::Move,
+    alternatives: I,
+) -> Result<()>
+where
+    R: Ruleset,
+    I: IntoIterator<Item = R::Move>,
+{
+    let must_feed = board.starving(player.next_player());
+    if must_feed && leaves_starving(rules, board, player, play) {
+        //If we must feed and didn't, we need to make sure that we couldn't have
+        let any_not_starving = alternatives
+            .into_iter()
+            .filter(|alternative| *alternative != play)
+            .any(|alternative| !leaves_starving(rules, board, player, alternative));
+        if any_not_starving {
+            return Err(MancalaError::MustFeedError);
+        }
+    };
+    Ok(())
+}
+
+/// Who has won once `player` has `mine` banked and their opponent `theirs`.
+pub(crate) fn winner(player: Player, mine: usize, theirs: usize) -> GameState {
+    match natural().compare(&mine, &theirs) {
+        Ordering::Less => GameState::Won(player.next_player()),
+        Ordering::Greater => GameState::Won(player),
+        Ordering::Equal => GameState::Draw,
+    }
+}
+
+/// Ends the game: `collector` banks the seeds left on the board, if anyone
+/// does, and the bigger bank wins.
+pub(crate) fn end_game(board: &mut MancalaBoard, collector: Option<Player>) -> GameState {
+    if let Some(collector) = collector {
+        for cup in board.cups().iter() {
+            board.pickup(CupPos::from(cup), collector);
+            board.bank(collector);
+        }
+    }
+    winner(
+        Player::Player1,
+        board.get_bank(Player::Player1),
+        board.get_bank(Player::Player2),
+    )
+}
+
+/// The capture for variants with stores in the sowing ring: ending in one of
+/// `player`'s own empty cups takes it and everything across from it.
+pub(crate) fn capture_opposite(board: &mut MancalaBoard, player: Player, last: Pit) {
+    match last {
+        Pit::Cup(last) if last.owner == player => {
+            if let Some(opposite) = board.topology().opposite(last) {
+                if board.seeds(Pit::Cup(last)) == 1 && board.seeds(Pit::Cup(opposite)) > 0 {
+                    board.pickup(last, player);
+                    board.pickup(opposite, player);
+                    board.bank(player);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Once either row is empty each player banks what is left on their side,
+/// and the bigger bank wins.
+pub(crate) fn sweep_sides(board: &mut MancalaBoard) -> Option<GameState> {
+    if !board.starving(Player::Player1) && !board.starving(Player::Player2) {
+        return None;
+    }
+    for cup in board.cups().iter() {
+        board.pickup(CupPos::from(cup), cup.owner);
+        board.bank(cup.owner);
+    }
+    Some(end_game(board, None))
+}
+
+/// Ends the turn for variants with stores in the sowing ring: the game is
+/// over once a row is empty, and ending in your own store earns another turn.
+pub(crate) fn store_turn(board: &mut MancalaBoard, player: Player, last: Pit) -> GameState {
+    if let Some(state) = sweep_sides(board) {
+        state
+    } else if last == Pit::Store(player) {
+        GameState::InProgress(player)
+    } else {
+        GameState::InProgress(player.next_player())
+    }
+}
+
+// What was played on a turn: one player's move, or both players' at once.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum Play<M> {
+    One(M),
+    Both(M, M),
+}
+
+// A turn that can be undone or redone, with the state it was played from or,
+// once undone, the state it led to.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Turn<M> {
+    state: GameState,
+    play: Play<M>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        try_from = "GameData<R>",
+        bound(
+            serialize = "R: Serialize, R::Move: Serialize",
+            deserialize = "R: Deserialize<'de>, R::Move: Deserialize<'de>"
+        )
+    )
+)]
+pub struct Game<R: Ruleset> {
+    pub(crate) rules: R,
+    pub(crate) board: MancalaBoard,
+    pub state: GameState,
+    history: Vec<Turn<R::Move>>,
+    undone: Vec<Turn<R::Move>>,
+}
+
+// What a game deserializes from before its turns are played again to check
+// them.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound = "R: Deserialize<'de>, R::Move: Deserialize<'de>")]
+struct GameData<R: Ruleset> {
+    rules: R,
+    board: MancalaBoard,
+    state: GameState,
+    history: Vec<Turn<R::Move>>,
+    undone: Vec<Turn<R::Move>>,
+}
+
+#[cfg(feature = "serde")]
+impl<R: Ruleset> std::convert::TryFrom<GameData<R>> for Game<R> {
+    type Error = MancalaError;
+
+    fn try_from(data: GameData<R>) -> Result<Game<R>> {
+        let GameData {
+            rules,
+            board,
+            state,
+            history,
+            undone,
+        } = data;
+        if rules.setup().topology() != board.topology()
+            || board.moves().len() != history.len() + 1
+            || board.undone.len() != undone.len()
+        {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let start = history.first().map_or(&state, |turn| &turn.state).clone();
+        match start {
+            GameState::InProgress(_) => (),
+            GameState::Simultaneous if rules.simultaneous_start() => (),
+            _ => return Err(MancalaError::InvalidBoard),
+        }
+        let mut start_board = board.clone();
+        for _ in history.iter() {
+            start_board.undo_move();
+        }
+        start_board.undone.clear();
+
+        let mut game = Game::with_board(rules, start_board, start);
+        for turn in history.iter().chain(undone.iter().rev()) {
+            game.replay(turn.play)
+                .map_err(|_| MancalaError::InvalidBoard)?;
+        }
+        for _ in undone.iter() {
+            game.undo();
+        }
+        if game.board != board
+            || game.state != state
+            || game.history != history
+            || game.undone != undone
+        {
+            return Err(MancalaError::InvalidBoard);
+        }
+        Ok(game)
+    }
+}
+
+impl<R: Ruleset + Default> Default for Game<R> {
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl<R: Ruleset> Game<R> {
+    pub fn new(rules: R) -> Game<R> {
+        let board = rules.setup();
+        let state = if rules.simultaneous_start() {
+            GameState::Simultaneous
+        } else {
+            GameState::InProgress(rules.first_player())
+        };
+        Game::with_board(rules, board, state)
+    }
+
+    pub(crate) fn with_board(rules: R, board: MancalaBoard, state: GameState) -> Game<R> {
+        Game {
+            rules,
+            board,
+            state,
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &R {
+        &self.rules
+    }
+
+    pub fn board(&self) -> &MancalaBoard {
+        &self.board
+    }
+
+    /// The board's Zobrist key along with whose turn it is, the same as the
+    /// key of `position` while the game is in progress.
+    pub fn zobrist(&self) -> u64 {
+        match self.state {
+            GameState::InProgress(player) => self.board.zobrist() ^ zobrist::to_move(player),
+            _ => self.board.zobrist(),
+        }
+    }
+
+    /// See `MancalaBoard::log_each_seed`.
+    pub fn log_each_seed(&mut self, on: bool) {
+        self.board.log_each_seed(on);
+    }
+
+    pub fn get_cups_for_player(&self, player: Player) -> Vec<Cup> {
+        self.board
+            .cups()
+            .into_iter()
+            .filter(|cup| cup.owner == player)
+            .collect::<Vec<_>>()
+    }
+
+    pub fn get_bank(&self, player: Player) -> usize {
+        self.board.get_bank(player)
+    }
+
+    /// The position the player whose turn it is has to move from, without
+    /// the history of how the game got there. None when it isn't one
+    /// player's turn, or the board is too big for a `Position`.
+    pub fn position(&self) -> Option<Position> {
+        match self.state {
+            GameState::InProgress(player) => Position::from_board(&self.board, player).ok(),
+            _ => None,
+        }
+    }
+
+    /// Every move the player whose turn it is could make, checked with the
+    /// same rules as `play`. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<R::Move> {
+        match self.state {
+            GameState::InProgress(player) => self.rules.legal_moves(&self.board, player),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn play(&mut self, play: R::Move) -> Result<()> {
+        let player = match self.state {
+            GameState::InProgress(p) => p,
+            GameState::Simultaneous => return Err(MancalaError::IllegalMove),
+            _ => return Ok(()),
+        };
+
+        self.rules.validate(&self.board, player, play)?;
+
+        let mut board = self.board.clone();
+        board.new_move();
+        let last = self.rules.sow(&mut board, player, play)?;
+        self.rules.capture(&mut board, player, last);
+        let state = self.rules.end_of_turn(&mut board, player, last);
+        self.finish_turn(board, state, Play::One(play));
+
+        Ok(())
+    }
+
+    /// Plays a move for each player at once. Both sowings are logged as a
+    /// single turn in the board's move history.
+    pub fn play_simultaneous(&mut self, player1: R::Move, player2: R::Move) -> Result<()> {
+        match self.state {
+            GameState::Simultaneous => (),
+            GameState::InProgress(_) => return Err(MancalaError::IllegalMove),
+            _ => return Ok(()),
+        }
+
+        self.rules.validate(&self.board, Player::Player1, player1)?;
+        self.rules.validate(&self.board, Player::Player2, player2)?;
+
+        let mut board = self.board.clone();
+        board.new_move();
+        let state = self.rules.sow_simultaneous(&mut board, player1, player2)?;
+        self.finish_turn(board, state, Play::Both(player1, player2));
+
+        Ok(())
+    }
+
+    fn finish_turn(&mut self, board: MancalaBoard, state: GameState, play: Play<R::Move>) {
+        self.board = board;
+        self.history.push(Turn {
+            state: std::mem::replace(&mut self.state, state),
+            play,
+        });
+        self.undone.clear();
+    }
+
+    // Plays a turn again, failing if it doesn't start a new one.
+    #[cfg(feature = "serde")]
+    fn replay(&mut self, play: Play<R::Move>) -> Result<()> {
+        let turns = self.history.len();
+        match play {
+            Play::One(play) => self.play(play)?,
+            Play::Both(player1, player2) => self.play_simultaneous(player1, player2)?,
+        }
+        if self.history.len() == turns {
+            return Err(MancalaError::IllegalMove);
+        }
+        Ok(())
+    }
+
+    /// Takes back the last turn. Returns false if there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if self.history.is_empty() || !self.board.undo_move() {
+            return false;
+        }
+        let turn = self.history.pop().expect("there is a turn to undo");
+        self.undone.push(Turn {
+            state: std::mem::replace(&mut self.state, turn.state),
+            play: turn.play,
+        });
+        true
+    }
+
+    /// Plays the last undone turn again. Returns false if there's nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.undone.is_empty() || !self.board.redo_move() {
+            return false;
+        }
+        let turn = self.undone.pop().expect("there is a turn to redo");
+        self.history.push(Turn {
+            state: std::mem::replace(&mut self.state, turn.state),
+            play: turn.play,
+        });
+        true
+    }
+}
+
+impl<R: Ruleset> fmt::Display for Game<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.board)
+    }
+}
+
+impl<R: Ruleset> fmt::Debug for Game<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.board)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::board::{Cup, CupPos, MancalaBoard, Pit, MAX_CUPS};
+use crate::game::{capture_opposite, store_turn, Ruleset};
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Kalah(pits, seeds), with each player's store sitting in the sowing ring
+/// after their own row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "KalahData", into = "KalahData")
+)]
+pub struct Kalah {
+    pits: usize,
+    seeds: usize,
+}
+
+// What the rules are serialized as, and deserialize from before they're
+// checked by `Kalah::new`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct KalahData {
+    pits: usize,
+    seeds: usize,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<KalahData> for Kalah {
+    type Error = MancalaError;
+
+    fn try_from(data: KalahData) -> Result<Kalah> {
+        Kalah::new(data.pits, data.seeds)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Kalah> for KalahData {
+    fn from(rules: Kalah) -> KalahData {
+        KalahData {
+            pits: rules.pits,
+            seeds: rules.seeds,
+        }
+    }
+}
+
+impl Default for Kalah {
+    fn default() -> Self {
+        Kalah { pits: 6, seeds: 4 }
+    }
+}
+
+impl Kalah {
+    /// Fails with `InvalidBoard` unless there's at least one cup a side and
+    /// the cups fit on a board.
+    pub fn new(pits: usize, seeds: usize) -> Result<Kalah> {
+        if pits == 0 || 2 * pits > MAX_CUPS {
+            return Err(MancalaError::InvalidBoard);
+        }
+        Ok(Kalah { pits, seeds })
+    }
+}
+
+impl Ruleset for Kalah {
+    type Move = usize;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..self.pits).map(move |i| Cup {
+                    owner: *player,
+                    seeds: self.seeds,
+                    pos: i,
+                })
+            })
+            .collect();
+
+        MancalaBoard::with_stores(board).expect("`Kalah::new` checked the board fits")
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, cup: usize) -> Result<()> {
+        match board.get_cup(CupPos {
+            owner: player,
+            pos: cup,
+        }) {
+            Some(Cup { seeds: 0, .. }) => Err(MancalaError::NoSeedsToSow),
+            None => Err(MancalaError::NoSuchCup),
+            _ => Ok(()),
+        }
+    }
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<usize> {
+        (0..self.pits)
+            .filter(|cup| self.validate(board, player, *cup).is_ok())
+            .collect()
+    }
+
+    fn sow(&self, board: &mut MancalaBoard, player: Player, cup: usize) -> Result<Pit> {
+        let cup = CupPos {
+            owner: player,
+            pos: cup,
+        };
+        board.pickup(cup, player);
+        board.sow(player, cup, |_, _, _| true)
+    }
+
+    // Ending in one of your own empty cups takes it and everything across from it.
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit) {
+        capture_opposite(board, player, last)
+    }
+
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, last: Pit) -> GameState {
+        store_turn(board, player, last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn extra_turn() -> Result<()> {
+        let mut game = Game::new(Kalah::default());
+        game.play(2)?;
+        assert_eq!("1 - ④|④|⓪|⑤|⑤|⑤\n④|④|④|④|④|④ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        game.play(5)?;
+        assert_eq!("2 - ④|④|⓪|⑤|⑤|⓪\n⑤|⑤|⑤|⑤|④|④ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn capture_opposite() -> Result<()> {
+        let mut game = Game::with_board(
+            Kalah::new(3, 2)?,
+            MancalaBoard::with_stores(Cup::rows(&[1, 0, 2], &[3, 4, 5]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(0)?;
+        assert_eq!("5 - ⓪|⓪|②\n③|⓪|⑤ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn no_capture_from_empty_cup() -> Result<()> {
+        let mut game = Game::with_board(
+            Kalah::new(3, 2)?,
+            MancalaBoard::with_stores(Cup::rows(&[1, 0, 2], &[3, 0, 5]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(0)?;
+        assert_eq!("0 - ⓪|①|②\n③|⓪|⑤ - 0", format!("{}", game));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_row_ends_game() -> Result<()> {
+        let mut game = Game::with_board(
+            Kalah::new(2, 1)?,
+            MancalaBoard::with_stores(Cup::rows(&[0, 1], &[2, 1]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(1)?;
+        assert_eq!("1 - ⓪|⓪\n⓪|⓪ - 3", format!("{}", game));
+        assert_eq!(game.state, GameState::Won(Player::Player2));
+        assert_eq!(
+            Err(MancalaError::NoSuchCup),
+            Kalah::new(2, 1)?.validate(game.board(), Player::Player1, 2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn checks_size() {
+        assert_eq!(Err(MancalaError::InvalidBoard), Kalah::new(0, 4));
+        assert_eq!(Err(MancalaError::InvalidBoard), Kalah::new(20, 4));
+        assert!(Kalah::new(16, 4).is_ok());
+    }
+}
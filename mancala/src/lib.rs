@@ -1,9 +1,28 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::error;
 use std::fmt;
+pub mod ai;
 pub mod ayoayo;
+pub mod bao;
 pub mod board;
+pub mod congkak;
+pub mod eval;
+pub mod game;
+pub mod kalah;
+pub mod mcts;
+pub mod oware;
+pub mod position;
+pub mod ranking;
+pub mod record;
+pub mod solver;
+pub mod tablebase;
+pub mod toguz_korgool;
+pub mod topology;
+mod zobrist;
 
 #[derive(Copy, Debug, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Player {
     Player1,
     Player2,
@@ -34,6 +53,13 @@ pub enum MancalaError {
     MustFeedError,
     NoSeedsToSow,
     NoSuchCup,
+    GrandSlam,
+    MustCapture,
+    IllegalMove,
+    /// A board or game that doesn't match its own move log.
+    InvalidBoard,
+    /// Text that isn't a position or game record, see `position` and `record`.
+    InvalidNotation,
 }
 
 impl fmt::Display for MancalaError {
@@ -44,6 +70,13 @@ impl fmt::Display for MancalaError {
             }
             MancalaError::NoSeedsToSow => write!(f, "You must choose a cup with seeds"),
             MancalaError::NoSuchCup => write!(f, "That cup doesn't exist"),
+            MancalaError::GrandSlam => {
+                write!(f, "You can't capture every one of your opponent's seeds")
+            }
+            MancalaError::MustCapture => write!(f, "You have to make a capture"),
+            MancalaError::IllegalMove => write!(f, "That move can't be played that way"),
+            MancalaError::InvalidBoard => write!(f, "That board isn't a position from a game"),
+            MancalaError::InvalidNotation => write!(f, "That isn't a position or game record"),
         }
     }
 }
@@ -56,8 +89,11 @@ impl error::Error for MancalaError {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameState {
     InProgress(Player),
+    /// Both players pick a move at the same time, see `Game::play_simultaneous`.
+    Simultaneous,
     Won(Player),
     Draw,
 }
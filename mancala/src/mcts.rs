@@ -0,0 +1,416 @@
+//! A Monte Carlo tree search player for any `Ruleset`, using UCT to pick
+//! which moves to look at and random playouts to judge them.
+//!
+//! Unlike `ai`, it doesn't need an evaluation of the board, so it copes
+//! with variants like Ayoayo where long relay sowings make it hard to tell
+//! who's ahead before the seeds settle.
+
+use crate::board::MancalaBoard;
+use crate::game::{Game, Ruleset};
+use crate::{GameState, MancalaError, Player, Result};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MctsConfig {
+    /// How much UCT favours moves it's tried less over ones that have done
+    /// well so far.
+    pub exploration: f64,
+    /// How many playouts to run at most.
+    pub playouts: usize,
+    /// When set, stops after the last playout started before the time runs
+    /// out.
+    pub time: Option<Duration>,
+    /// Turns a playout can last before the banks decide it, since some
+    /// games can go round forever.
+    pub playout_turns: usize,
+    /// Seeds the random playouts, so the same search always picks the same move.
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            playouts: 1000,
+            time: None,
+            playout_turns: 200,
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsResult<M> {
+    /// The move that was tried the most.
+    pub best: M,
+    /// How many playouts went through each legal move, none for moves that
+    /// weren't tried.
+    pub visits: Vec<(M, usize)>,
+    /// The share of playouts through `best` that its player won, with draws
+    /// counting half.
+    pub win_rate: f64,
+    pub playouts: usize,
+}
+
+/// Picks a move for the player whose turn it is in `game`, or None when
+/// it isn't one player's turn or nothing can be played.
+pub fn search<R: Ruleset>(game: &Game<R>, config: &MctsConfig) -> Option<MctsResult<R::Move>> {
+    if !matches!(game.state, GameState::InProgress(_)) {
+        return None;
+    }
+    let deadline = config.time.map(|time| Instant::now() + time);
+    let root = State {
+        rules: game.rules(),
+        board: game.board().without_log(),
+        state: game.state.clone(),
+    };
+    let mut tree = Tree {
+        nodes: vec![Node::new(None, None, &root)],
+        exploration: config.exploration,
+        playout_turns: config.playout_turns,
+        rng: Rng::new(config.seed),
+    };
+
+    let mut playouts = 0;
+    while playouts < config.playouts && deadline.is_none_or(|deadline| Instant::now() < deadline) {
+        tree.iterate(root.clone());
+        playouts += 1;
+    }
+
+    let children = &tree.nodes[0].children;
+    let best = children
+        .iter()
+        .map(|child| &tree.nodes[*child])
+        .max_by_key(|node| node.visits)?;
+    Some(MctsResult {
+        best: best.play?,
+        visits: root
+            .legal_moves()
+            .into_iter()
+            .map(|play| {
+                let visits = children
+                    .iter()
+                    .map(|child| &tree.nodes[*child])
+                    .find(|node| node.play == Some(play))
+                    .map_or(0, |node| node.visits);
+                (play, visits)
+            })
+            .collect(),
+        win_rate: best.wins / best.visits.max(1) as f64,
+        playouts,
+    })
+}
+
+struct Node<M> {
+    // The move that led here and the player who made it.
+    play: Option<M>,
+    player: Option<Player>,
+    children: Vec<usize>,
+    untried: Vec<M>,
+    visits: usize,
+    // Playouts `player` won, with draws counting half.
+    wins: f64,
+}
+
+impl<M: Copy> Node<M> {
+    fn new<R: Ruleset<Move = M>>(play: Option<M>, player: Option<Player>, game: &State<R>) -> Self {
+        Node {
+            play,
+            player,
+            children: Vec::new(),
+            untried: game.legal_moves(),
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+struct Tree<M> {
+    nodes: Vec<Node<M>>,
+    exploration: f64,
+    playout_turns: usize,
+    rng: Rng,
+}
+
+impl<M: Copy> Tree<M> {
+    // Runs one playout: down the tree by UCT, out by one new move, then at
+    // random to the end of the game, and back up with the result.
+    fn iterate<R: Ruleset<Move = M>>(&mut self, mut game: State<R>) {
+        let mut path = vec![0];
+        let mut node = 0;
+        while let GameState::InProgress(player) = game.state {
+            if let Some(play) = self.untried_move(node) {
+                if game.play(play).is_err() {
+                    continue;
+                }
+                let child = self.nodes.len();
+                self.nodes.push(Node::new(Some(play), Some(player), &game));
+                self.nodes[node].children.push(child);
+                path.push(child);
+                break;
+            }
+            match self.select(node) {
+                Some(child) => {
+                    let play = self.nodes[child].play.expect("only the root has no move");
+                    if game.play(play).is_err() {
+                        break;
+                    }
+                    node = child;
+                    path.push(child);
+                }
+                None => break,
+            }
+        }
+
+        let result = self.playout(&mut game);
+        for node in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.wins += match (&result, node.player) {
+                (GameState::Won(winner), Some(player)) if *winner == player => 1.0,
+                (GameState::Draw, _) => 0.5,
+                _ => 0.0,
+            };
+        }
+    }
+
+    // Takes a random move that hasn't been tried from `node` yet.
+    fn untried_move(&mut self, node: usize) -> Option<M> {
+        let untried = &mut self.nodes[node].untried;
+        if untried.is_empty() {
+            return None;
+        }
+        let pick = self.rng.below(untried.len());
+        Some(untried.swap_remove(pick))
+    }
+
+    // The child of `node` with the best upper confidence bound.
+    fn select(&self, node: usize) -> Option<usize> {
+        let parent = &self.nodes[node];
+        let log_visits = (parent.visits.max(1) as f64).ln();
+        let bound = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits.max(1) as f64;
+            child.wins / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+        parent
+            .children
+            .iter()
+            .copied()
+            .max_by(|a, b| bound(*a).total_cmp(&bound(*b)))
+    }
+
+    // Plays random moves to the end of the game, or until the turns run out
+    // and whoever has the most in their bank is taken to have won.
+    fn playout<R: Ruleset<Move = M>>(&mut self, game: &mut State<R>) -> GameState {
+        for _ in 0..self.playout_turns {
+            let played = match game.state {
+                GameState::InProgress(_) => {
+                    let mut moves = game.legal_moves();
+                    let mut played = false;
+                    while !moves.is_empty() && !played {
+                        let play = moves.swap_remove(self.rng.below(moves.len()));
+                        played = game.play(play).is_ok();
+                    }
+                    played
+                }
+                GameState::Simultaneous => {
+                    let mut pick = |player| {
+                        let moves = game.rules.legal_moves(&game.board, player);
+                        match moves.len() {
+                            0 => None,
+                            len => Some(moves[self.rng.below(len)]),
+                        }
+                    };
+                    match (pick(Player::Player1), pick(Player::Player2)) {
+                        (Some(player1), Some(player2)) => {
+                            game.play_simultaneous(player1, player2).is_ok()
+                        }
+                        _ => false,
+                    }
+                }
+                _ => return game.state.clone(),
+            };
+            if !played {
+                break;
+            }
+        }
+        let (player1, player2) = (
+            game.board.get_bank(Player::Player1),
+            game.board.get_bank(Player::Player2),
+        );
+        match game.state {
+            GameState::Won(_) | GameState::Draw => game.state.clone(),
+            _ if player1 > player2 => GameState::Won(Player::Player1),
+            _ if player2 > player1 => GameState::Won(Player::Player2),
+            _ => GameState::Draw,
+        }
+    }
+}
+
+// A game on a board with no move log, played a turn at a time through the
+// `Ruleset` the way `Game` plays it, so copying one for each playout doesn't
+// copy the history and undo stack a `Game` keeps.
+struct State<'a, R: Ruleset> {
+    rules: &'a R,
+    board: MancalaBoard,
+    state: GameState,
+}
+
+impl<R: Ruleset> Clone for State<'_, R> {
+    fn clone(&self) -> Self {
+        State {
+            rules: self.rules,
+            board: self.board.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<R: Ruleset> State<'_, R> {
+    fn legal_moves(&self) -> Vec<R::Move> {
+        match self.state {
+            GameState::InProgress(player) => self.rules.legal_moves(&self.board, player),
+            _ => Vec::new(),
+        }
+    }
+
+    // Like `Game::play`, the board is left alone if the move fails.
+    fn play(&mut self, play: R::Move) -> Result<()> {
+        let player = match self.state {
+            GameState::InProgress(player) => player,
+            _ => return Err(MancalaError::IllegalMove),
+        };
+        self.rules.validate(&self.board, player, play)?;
+        let mut board = self.board.clone();
+        let last = self.rules.sow(&mut board, player, play)?;
+        self.rules.capture(&mut board, player, last);
+        self.state = self.rules.end_of_turn(&mut board, player, last);
+        self.board = board;
+        Ok(())
+    }
+
+    fn play_simultaneous(&mut self, player1: R::Move, player2: R::Move) -> Result<()> {
+        if self.state != GameState::Simultaneous {
+            return Err(MancalaError::IllegalMove);
+        }
+        self.rules.validate(&self.board, Player::Player1, player1)?;
+        self.rules.validate(&self.board, Player::Player2, player2)?;
+        let mut board = self.board.clone();
+        self.state = self.rules.sow_simultaneous(&mut board, player1, player2)?;
+        self.board = board;
+        Ok(())
+    }
+}
+
+// xorshift64*, which is plenty for picking random moves.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // Zero would stay zero forever.
+        Rng(seed | 1)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::{Ayoayo, AyoayoConfig};
+    use crate::kalah::Kalah;
+    use crate::position::Position;
+
+    #[test]
+    fn counts_visits() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        game.play(3)?;
+        let config = MctsConfig {
+            playouts: 200,
+            ..MctsConfig::default()
+        };
+        let result = search(&game, &config).unwrap();
+        assert_eq!(200, result.playouts);
+        let mut cups: Vec<usize> = result.visits.iter().map(|(cup, _)| *cup).collect();
+        cups.sort_unstable();
+        assert_eq!(game.legal_moves(), cups);
+        assert_eq!(
+            200,
+            result
+                .visits
+                .iter()
+                .map(|(_, visits)| visits)
+                .sum::<usize>()
+        );
+        assert_eq!(result, search(&game, &config).unwrap());
+
+        let config = MctsConfig {
+            playouts: 2,
+            ..MctsConfig::default()
+        };
+        let result = search(&game, &config).unwrap();
+        assert_eq!(
+            game.legal_moves(),
+            result
+                .visits
+                .iter()
+                .map(|(cup, _)| *cup)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            game.legal_moves().len() - 2,
+            result
+                .visits
+                .iter()
+                .filter(|(_, visits)| *visits == 0)
+                .count()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn takes_a_win() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })?;
+        // Sowing the first cup captures all of Player 2's seeds and ends the game.
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        let game = position.to_game(rules)?;
+        let result = search(&game, &MctsConfig::default()).unwrap();
+        assert_eq!(0, result.best);
+        assert!(result.win_rate > 0.99);
+        Ok(())
+    }
+
+    #[test]
+    fn any_ruleset() -> Result<()> {
+        let mut game = Game::new(Kalah::default());
+        game.play(2)?;
+        let result = search(&game, &MctsConfig::default()).unwrap();
+        assert!(game.legal_moves().contains(&result.best));
+
+        game.state = GameState::Draw;
+        assert_eq!(None, search(&game, &MctsConfig::default()));
+        Ok(())
+    }
+
+    #[test]
+    fn time_budget() {
+        let game = Game::new(Ayoayo::new());
+        let config = MctsConfig {
+            playouts: usize::MAX,
+            time: Some(Duration::from_millis(50)),
+            ..MctsConfig::default()
+        };
+        let result = search(&game, &config).unwrap();
+        assert!(result.playouts > 0);
+        assert!(game.legal_moves().contains(&result.best));
+    }
+}
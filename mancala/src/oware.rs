@@ -0,0 +1,285 @@
+use crate::board::{skip_origin, Cup, CupPos, MancalaBoard, Pit};
+use crate::game::{end_game, leaves_starving, must_feed, Ruleset};
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const PITS: usize = 6;
+const STARTING_COUNT: usize = 4;
+const WINNING_COUNT: usize = 25;
+
+/// What happens to a move that would capture every seed the opponent has left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GrandSlam {
+    /// The move can't be played, unless every other move is a grand slam too.
+    Forbidden,
+    /// The move is played, but nothing is captured.
+    NoCapture,
+}
+
+/// Oware Abapa: single lap sowing, captures of 2s and 3s in the opponent's row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Oware {
+    grand_slam: GrandSlam,
+}
+
+impl Default for Oware {
+    fn default() -> Self {
+        Oware::new(GrandSlam::NoCapture)
+    }
+}
+
+impl Oware {
+    pub fn new(grand_slam: GrandSlam) -> Oware {
+        Oware { grand_slam }
+    }
+
+    // Walks back from the last cup through the opponent's row while cups hold 2 or 3 seeds.
+    fn captures(&self, board: &MancalaBoard, player: Player, last: Pit) -> Vec<CupPos> {
+        let mut captured = Vec::new();
+        if let Pit::Cup(last) = last {
+            if last.owner != player {
+                for pos in (0..=last.pos).rev() {
+                    let cup = CupPos {
+                        owner: last.owner,
+                        pos,
+                    };
+                    match board.seeds(Pit::Cup(cup)) {
+                        2 | 3 => captured.push(cup),
+                        _ => break,
+                    }
+                }
+            }
+        }
+        captured
+    }
+
+    fn is_grand_slam(&self, board: &MancalaBoard, player: Player, captured: &[CupPos]) -> bool {
+        !captured.is_empty()
+            && board
+                .cups()
+                .iter()
+                .filter(|cup| cup.owner != player && cup.seeds > 0)
+                .all(|cup| captured.contains(&CupPos::from(cup)))
+    }
+
+    fn grand_slams(&self, board: &MancalaBoard, player: Player, cup: usize) -> bool {
+        let mut board = board.clone();
+        match self.sow(&mut board, player, cup) {
+            Ok(last) => {
+                let captured = self.captures(&board, player, last);
+                self.is_grand_slam(&board, player, &captured)
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn playable(&self, board: &MancalaBoard, player: Player, cup: usize) -> bool {
+        board.seeds(Pit::Cup(CupPos {
+            owner: player,
+            pos: cup,
+        })) > 0
+            && must_feed(self, board, player, cup, 0..PITS).is_ok()
+    }
+
+    fn can_feed(&self, board: &MancalaBoard, player: Player) -> bool {
+        (0..PITS).any(|cup| {
+            board.seeds(Pit::Cup(CupPos {
+                owner: player,
+                pos: cup,
+            })) > 0
+                && !leaves_starving(self, board, player, cup)
+        })
+    }
+}
+
+impl Ruleset for Oware {
+    type Move = usize;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..PITS).map(move |i| Cup {
+                    owner: *player,
+                    seeds: STARTING_COUNT,
+                    pos: i,
+                })
+            })
+            .collect();
+
+        MancalaBoard::new(board).expect("an Oware board fits")
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, cup: usize) -> Result<()> {
+        if cup >= PITS {
+            return Err(MancalaError::NoSuchCup);
+        }
+
+        match board.get_cup(CupPos {
+            owner: player,
+            pos: cup,
+        }) {
+            Some(Cup { seeds: 0, .. }) => return Err(MancalaError::NoSeedsToSow),
+            None => return Err(MancalaError::NoSuchCup),
+            _ => (),
+        };
+
+        must_feed(self, board, player, cup, 0..PITS)?;
+
+        if self.grand_slam == GrandSlam::Forbidden && self.grand_slams(board, player, cup) {
+            let any_other = (0..PITS).filter(|other| *other != cup).any(|other| {
+                self.playable(board, player, other) && !self.grand_slams(board, player, other)
+            });
+            if any_other {
+                return Err(MancalaError::GrandSlam);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<usize> {
+        (0..PITS)
+            .filter(|cup| self.validate(board, player, *cup).is_ok())
+            .collect()
+    }
+
+    fn sow(&self, board: &mut MancalaBoard, player: Player, cup: usize) -> Result<Pit> {
+        let cup = CupPos {
+            owner: player,
+            pos: cup,
+        };
+        board.pickup(cup, player);
+        board.sow(player, cup, skip_origin)
+    }
+
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit) {
+        let captured = self.captures(board, player, last);
+        if self.is_grand_slam(board, player, &captured) {
+            return;
+        }
+        for cup in captured {
+            board.pickup(cup, player);
+        }
+        board.bank(player);
+    }
+
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, _last: Pit) -> GameState {
+        let opponent = player.next_player();
+        if board.get_bank(player) >= WINNING_COUNT {
+            GameState::Won(player)
+        } else if board.get_bank(player) == WINNING_COUNT - 1
+            && board.get_bank(opponent) == WINNING_COUNT - 1
+        {
+            GameState::Draw
+        } else if board.starving(opponent) {
+            // Must feed already made us feed them if we could.
+            end_game(board, Some(player))
+        } else if board.starving(player) && !self.can_feed(board, opponent) {
+            end_game(board, Some(opponent))
+        } else {
+            GameState::InProgress(opponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn single_lap() -> Result<()> {
+        let mut game = Game::new(Oware::default());
+        game.play(2)?;
+        assert_eq!("0 - ④|④|⓪|⑤|⑤|⑤\n⑤|④|④|④|④|④ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn capture_backwards() -> Result<()> {
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[0, 0, 0, 0, 0, 3], &[1, 2, 1, 4, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(5)?;
+        assert_eq!("7 - ⓪|⓪|⓪|⓪|⓪|⓪\n⓪|⓪|⓪|④|⓪|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[0, 0, 0, 0, 0, 3], &[1, 4, 1, 4, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(5)?;
+        assert_eq!("2 - ⓪|⓪|⓪|⓪|⓪|⓪\n②|⑤|⓪|④|⓪|⓪ - 0", format!("{}", game));
+        Ok(())
+    }
+
+    #[test]
+    fn grand_slam() -> Result<()> {
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[1, 0, 0, 0, 0, 2], &[1, 2, 0, 0, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(5)?;
+        assert_eq!("0 - ①|⓪|⓪|⓪|⓪|⓪\n②|③|⓪|⓪|⓪|⓪ - 0", format!("{}", game));
+
+        let mut game = Game::with_board(
+            Oware::new(GrandSlam::Forbidden),
+            MancalaBoard::new(Cup::rows(&[1, 0, 0, 0, 0, 2], &[1, 2, 0, 0, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        assert_eq!(Err(MancalaError::GrandSlam), game.play(5));
+        assert_eq!(
+            vec![0],
+            game.rules().legal_moves(game.board(), Player::Player1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn majority_wins() -> Result<()> {
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[0, 0, 0, 0, 1, 3], &[1, 2, 1, 4, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.board.deposit(Player::Player1, 20);
+        game.play(5)?;
+        assert_eq!(game.state, GameState::Won(Player::Player1));
+        Ok(())
+    }
+
+    #[test]
+    fn must_feed_test() -> Result<()> {
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[1, 0, 0, 0, 0, 1], &[0, 0, 0, 0, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        assert_eq!(Err(MancalaError::MustFeedError), game.play(0));
+        game.play(5)?;
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_feed() -> Result<()> {
+        let mut game = Game::with_board(
+            Oware::default(),
+            MancalaBoard::new(Cup::rows(&[1, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 0, 0]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(0)?;
+        assert_eq!("1 - ⓪|⓪|⓪|⓪|⓪|⓪\n⓪|⓪|⓪|⓪|⓪|⓪ - 0", format!("{}", game));
+        assert_eq!(game.state, GameState::Won(Player::Player1));
+        Ok(())
+    }
+}
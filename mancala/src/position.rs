@@ -0,0 +1,354 @@
+//! A position is what's on the board between turns, without the history
+//! of how it got there.
+//!
+//! Its text form lists each player's cups in sowing order, then the banks,
+//! the seeds in hand and the side to move, with `/` between the two players:
+//!
+//! ```text
+//! 4,4,4,4,4,4/4,4,4,4,4,4 0/0 0/0 1
+//! ```
+//!
+//! Houses and tuzdyks aren't part of a position.
+
+use crate::board::{Cup, MancalaBoard, MAX_CUPS};
+use crate::game::{Game, Ruleset};
+use crate::{zobrist, GameState, MancalaError, Player, Result};
+use itertools::Itertools;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "PositionData", into = "PositionData")
+)]
+pub struct Position {
+    // Seeds in each cup, Player 1's in sowing order and then Player 2's,
+    // with the cups past `cups` left empty.
+    pub(crate) seeds: [u16; MAX_CUPS],
+    cups: u8,
+    pub banks: [usize; 2],
+    pub in_hand: [usize; 2],
+    pub to_move: Player,
+}
+
+// What a position is serialized as, and deserializes from before it's
+// checked by `Position::new`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct PositionData {
+    seeds: Vec<usize>,
+    banks: [usize; 2],
+    in_hand: [usize; 2],
+    to_move: Player,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PositionData> for Position {
+    type Error = MancalaError;
+
+    fn try_from(data: PositionData) -> Result<Position> {
+        Position::new(&data.seeds, data.banks, data.in_hand, data.to_move)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Position> for PositionData {
+    fn from(position: Position) -> PositionData {
+        PositionData {
+            seeds: position
+                .seeds()
+                .iter()
+                .map(|seeds| *seeds as usize)
+                .collect(),
+            banks: position.banks,
+            in_hand: position.in_hand,
+            to_move: position.to_move,
+        }
+    }
+}
+
+fn index(player: Player) -> usize {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => 1,
+    }
+}
+
+impl Position {
+    /// Fails with `InvalidBoard` unless there are cups and both players have
+    /// the same number, for more than `MAX_CUPS` cups, or more seeds in a cup
+    /// than a position can count.
+    pub fn new(
+        seeds: &[usize],
+        banks: [usize; 2],
+        in_hand: [usize; 2],
+        to_move: Player,
+    ) -> Result<Position> {
+        if seeds.is_empty() || !seeds.len().is_multiple_of(2) || seeds.len() > MAX_CUPS {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let mut cups = [0; MAX_CUPS];
+        for (cup, seeds) in cups.iter_mut().zip(seeds.iter()) {
+            *cup = u16::try_from(*seeds).map_err(|_| MancalaError::InvalidBoard)?;
+        }
+        Ok(Position {
+            seeds: cups,
+            cups: seeds.len() as u8,
+            banks,
+            in_hand,
+            to_move,
+        })
+    }
+
+    /// Reads the position off `board`, with `to_move` to play next.
+    pub fn from_board(board: &MancalaBoard, to_move: Player) -> Result<Position> {
+        let seeds: Vec<usize> = board.cups().iter().map(|cup| cup.seeds).collect();
+        let players = [Player::Player1, Player::Player2];
+        Position::new(
+            &seeds,
+            [board.get_bank(players[0]), board.get_bank(players[1])],
+            [board.in_hand(players[0]), board.in_hand(players[1])],
+            to_move,
+        )
+    }
+
+    /// Seeds in each cup, Player 1's in sowing order and then Player 2's.
+    pub fn seeds(&self) -> &[u16] {
+        &self.seeds[..self.cups as usize]
+    }
+
+    /// The Zobrist key of the position, matching `Game::zobrist`.
+    pub fn zobrist(&self) -> u64 {
+        let cups = self
+            .seeds()
+            .iter()
+            .enumerate()
+            .fold(0, |hash, (index, seeds)| {
+                hash ^ zobrist::cup(index, *seeds as usize)
+            });
+        [Player::Player1, Player::Player2]
+            .iter()
+            .fold(cups, |hash, player| {
+                hash ^ zobrist::bank(*player, self.bank(*player))
+            })
+            ^ zobrist::to_move(self.to_move)
+    }
+
+    pub fn bank(&self, player: Player) -> usize {
+        self.banks[index(player)]
+    }
+
+    pub(crate) fn bank_mut(&mut self, player: Player) -> &mut usize {
+        &mut self.banks[index(player)]
+    }
+
+    /// Sets up a game of `rules` at this position. The board is laid out the
+    /// way `rules` sets it up, so the number of cups has to match, and turns
+    /// can't start with seeds in hand.
+    pub fn to_game<R: Ruleset>(&self, rules: R) -> Result<Game<R>> {
+        let setup = rules.setup();
+        if setup.cups().len() != self.seeds().len() || self.in_hand != [0, 0] {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let cups: Vec<Cup> = setup
+            .cups()
+            .iter()
+            .zip(self.seeds().iter())
+            .map(|(cup, seeds)| Cup {
+                seeds: *seeds as usize,
+                ..*cup
+            })
+            .collect();
+        let mut board = MancalaBoard::with_topology(cups, setup.topology())?;
+        for player in [Player::Player1, Player::Player2].iter() {
+            board.fill_store(*player, self.bank(*player));
+        }
+        Ok(Game::with_board(
+            rules,
+            board,
+            GameState::InProgress(self.to_move),
+        ))
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (player1, player2) = self.seeds().split_at(self.seeds().len() / 2);
+        write!(
+            fmt,
+            "{}/{} {}/{} {}/{} {}",
+            player1.iter().join(","),
+            player2.iter().join(","),
+            self.banks[0],
+            self.banks[1],
+            self.in_hand[0],
+            self.in_hand[1],
+            match self.to_move {
+                Player::Player1 => 1,
+                Player::Player2 => 2,
+            }
+        )
+    }
+}
+
+// Reads a `/` separated pair, like the banks or the seeds in hand.
+fn pair(field: Option<&str>) -> Result<[usize; 2]> {
+    let (first, second) = field
+        .and_then(|field| field.split('/').collect_tuple())
+        .ok_or(MancalaError::InvalidNotation)?;
+    Ok([number(first)?, number(second)?])
+}
+
+fn number(field: &str) -> Result<usize> {
+    field.parse().map_err(|_| MancalaError::InvalidNotation)
+}
+
+impl FromStr for Position {
+    type Err = MancalaError;
+
+    fn from_str(notation: &str) -> Result<Position> {
+        let mut fields = notation.split_whitespace();
+        let (player1, player2) = fields
+            .next()
+            .and_then(|cups| cups.split('/').collect_tuple())
+            .ok_or(MancalaError::InvalidNotation)?;
+        let row = |cups: &str| -> Result<Vec<usize>> { cups.split(',').map(number).collect() };
+        let (player1, player2) = (row(player1)?, row(player2)?);
+        if player1.len() != player2.len() {
+            return Err(MancalaError::InvalidNotation);
+        }
+        let banks = pair(fields.next())?;
+        let in_hand = pair(fields.next())?;
+        let to_move = match fields.next() {
+            Some("1") => Player::Player1,
+            Some("2") => Player::Player2,
+            _ => return Err(MancalaError::InvalidNotation),
+        };
+        if fields.next().is_some() {
+            return Err(MancalaError::InvalidNotation);
+        }
+        let seeds: Vec<usize> = player1.into_iter().chain(player2).collect();
+        Position::new(&seeds, banks, in_hand, to_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::Ayoayo;
+    use crate::kalah::Kalah;
+    use std::collections::HashSet;
+
+    #[test]
+    fn notation() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        let start = game.position().unwrap();
+        assert_eq!("4,4,4,4,4,4/4,4,4,4,4,4 0/0 0/0 1", start.to_string());
+        assert_eq!(start, start.to_string().parse()?);
+
+        game.play(3)?;
+        let position = game.position().unwrap();
+        assert_eq!("1,6,6,2,7,1/6,1,6,6,6,0 0/0 0/0 2", position.to_string());
+        assert_eq!(position, position.to_string().parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn to_game() -> Result<()> {
+        let position: Position = "2,1,0/2,0,3 5/7 0/0 1".parse()?;
+        let mut game = position.to_game(Kalah::new(3, 2)?)?;
+        assert_eq!("5 - ②|①|⓪\n②|⓪|③ - 7", format!("{}", game));
+        game.play(0)?;
+        assert_eq!(
+            "0,2,0/0,0,3 8/7 0/0 2",
+            game.position().unwrap().to_string()
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            position.to_game(Kalah::default()).map(|_| ())
+        );
+        let holding: Position = "2,1,0/2,0,3 5/7 1/0 1".parse()?;
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            holding.to_game(Kalah::new(3, 2)?).map(|_| ())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_history() -> Result<()> {
+        let mut played = Game::new(Ayoayo::new());
+        played.play(3)?;
+        let position = played.position().unwrap();
+        let set_up = position.to_game(Ayoayo::new())?;
+        assert_ne!(played.board(), set_up.board());
+        assert_eq!(Some(position), set_up.position());
+
+        assert_ne!(
+            played.board().zobrist(),
+            Game::new(Ayoayo::new()).board().zobrist()
+        );
+        assert_eq!(played.zobrist(), set_up.zobrist());
+        assert_eq!(played.zobrist(), position.zobrist());
+
+        let mut seen = HashSet::new();
+        seen.insert(position);
+        assert!(seen.contains(&set_up.position().unwrap()));
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            Position::new(&[0; MAX_CUPS + 1], [0, 0], [0, 0], Player::Player1)
+        );
+        for seeds in [&[][..], &[1, 2, 3][..]].iter() {
+            assert_eq!(
+                Err(MancalaError::InvalidBoard),
+                Position::new(seeds, [0, 0], [0, 0], Player::Player1)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bad_notation() {
+        for notation in [
+            "",
+            "1,2/3 0/0 0/0 1",
+            "/ 0/0 0/0 1",
+            "1,2/3,x 0/0 0/0 1",
+            "1,2/3,4 0 0/0 1",
+            "1,2/3,4 0/0 0/0 3",
+            "1,2/3,4 0/0 0/0 1 extra",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Err(MancalaError::InvalidNotation),
+                notation.parse::<Position>(),
+                "{}",
+                notation
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_checks_cups() -> Result<()> {
+        let position: Position = "2,1,0/2,0,3 5/7 0/0 1".parse()?;
+        let json = serde_json::to_string(&position).unwrap();
+        assert_eq!(position, serde_json::from_str(&json).unwrap());
+
+        let too_many = format!(
+            r#"{{"seeds":[{}],"banks":[0,0],"in_hand":[0,0],"to_move":"Player1"}}"#,
+            vec!["1"; 200].join(",")
+        );
+        assert!(serde_json::from_str::<Position>(&too_many).is_err());
+        let odd = json.replace("[2,1,0,2,0,3]", "[2,1,0,2,0]");
+        assert_ne!(json, odd);
+        assert!(serde_json::from_str::<Position>(&odd).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Numbers every position with up to a given number of seeds on the board,
+//! from 0 up with none missed, and back again.
+//!
+//! Positions are ordered by the seeds on the board, then by the seeds in
+//! the first cup, the second and so on, with Player 1 to move before Player
+//! 2. Counting the boards with `n` seeds in `k` cups is stars and bars:
+//! `C(n + k - 1, k - 1)` ways. Banks and seeds in hand aren't part of the
+//! number.
+
+use crate::board::MAX_CUPS;
+use crate::game::Ruleset;
+use crate::position::Position;
+use crate::{MancalaError, Player, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ranking {
+    cups: usize,
+    max_seeds: usize,
+    // Binomial coefficients, `choose[n][k]` for `k` up to `cups`.
+    choose: Vec<Vec<u64>>,
+}
+
+impl Ranking {
+    /// Ranks boards of `cups` cups with up to `max_seeds` on them. Fails with
+    /// `InvalidBoard` for more cups than a `Position` holds, or more
+    /// positions than fit in a `u64`.
+    pub fn new(cups: usize, max_seeds: usize) -> Result<Ranking> {
+        if cups == 0 || cups > MAX_CUPS {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let rows = max_seeds + cups;
+        let mut choose = vec![vec![0u64; cups + 1]; rows + 1];
+        for n in 0..=rows {
+            choose[n][0] = 1;
+            for k in 1..=cups.min(n) {
+                choose[n][k] = choose[n - 1][k - 1]
+                    .checked_add(choose[n - 1][k])
+                    .ok_or(MancalaError::InvalidBoard)?;
+            }
+        }
+        choose[rows][cups]
+            .checked_mul(2)
+            .ok_or(MancalaError::InvalidBoard)?;
+        Ok(Ranking {
+            cups,
+            max_seeds,
+            choose,
+        })
+    }
+
+    /// Ranks the boards `rules` sets up, with up to `max_seeds` on them.
+    pub fn for_rules<R: Ruleset>(rules: &R, max_seeds: usize) -> Result<Ranking> {
+        Ranking::new(rules.setup().cups().len(), max_seeds)
+    }
+
+    pub fn cups(&self) -> usize {
+        self.cups
+    }
+
+    pub fn max_seeds(&self) -> usize {
+        self.max_seeds
+    }
+
+    /// How many positions there are, so every rank is below it.
+    pub fn positions(&self) -> u64 {
+        2 * self.boards_below(self.max_seeds + 1)
+    }
+
+    /// How many boards have exactly `seeds` on them.
+    pub fn boards(&self, seeds: usize) -> u64 {
+        self.with_seeds(seeds, self.cups)
+    }
+
+    /// How many boards have fewer than `seeds` on them, which is where the
+    /// boards with `seeds` start.
+    pub fn boards_below(&self, seeds: usize) -> u64 {
+        match seeds {
+            0 => 0,
+            _ => self.up_to(seeds - 1, self.cups),
+        }
+    }
+
+    /// Where `board` comes among the boards with as many seeds as it has.
+    /// `board` needs `cups` cups and at most `max_seeds` on it.
+    pub(crate) fn rank_board(&self, board: &[u16]) -> u64 {
+        let mut left: usize = board.iter().map(|seeds| *seeds as usize).sum();
+        let mut rank = 0;
+        for (cup, seeds) in board.iter().enumerate().take(self.cups - 1) {
+            let seeds = *seeds as usize;
+            let after = self.cups - cup - 1;
+            // Boards with fewer seeds in this cup come first.
+            rank += self.up_to(left, after) - self.up_to(left - seeds, after);
+            left -= seeds;
+        }
+        rank
+    }
+
+    /// The board ranked `rank` among the ones with `seeds` on them, which
+    /// needs `rank` below `boards(seeds)` and `seeds` up to `max_seeds`.
+    pub(crate) fn unrank_board(&self, mut rank: u64, seeds: usize) -> Vec<usize> {
+        let mut board = Vec::with_capacity(self.cups);
+        let mut left = seeds;
+        for cup in 0..self.cups - 1 {
+            let after = self.cups - cup - 1;
+            let mut count = 0;
+            while rank >= self.with_seeds(left - count, after) {
+                rank -= self.with_seeds(left - count, after);
+                count += 1;
+            }
+            board.push(count);
+            left -= count;
+        }
+        board.push(left);
+        board
+    }
+
+    /// The number of `position`, or None if it has the wrong number of cups
+    /// or too many seeds.
+    pub fn rank(&self, position: &Position) -> Option<u64> {
+        let board = position.seeds();
+        let seeds: usize = board.iter().map(|seeds| *seeds as usize).sum();
+        if board.len() != self.cups || seeds > self.max_seeds {
+            return None;
+        }
+        let side = match position.to_move {
+            Player::Player1 => 0,
+            Player::Player2 => 1,
+        };
+        Some(2 * (self.boards_below(seeds) + self.rank_board(board)) + side)
+    }
+
+    /// The position numbered `rank`, with nothing banked or in hand, or None
+    /// if `rank` is past the last one.
+    pub fn unrank(&self, rank: u64) -> Option<Position> {
+        if rank >= self.positions() {
+            return None;
+        }
+        let to_move = match rank % 2 {
+            0 => Player::Player1,
+            _ => Player::Player2,
+        };
+        let board = rank / 2;
+        let seeds = (0..=self.max_seeds).find(|seeds| board < self.boards_below(seeds + 1))?;
+        let board = self.unrank_board(board - self.boards_below(seeds), seeds);
+        Position::new(&board, [0, 0], [0, 0], to_move).ok()
+    }
+
+    // Ways to put exactly `seeds` seeds in `cups` cups.
+    fn with_seeds(&self, seeds: usize, cups: usize) -> u64 {
+        match cups {
+            0 => (seeds == 0) as u64,
+            _ => self.choose[seeds + cups - 1][cups - 1],
+        }
+    }
+
+    // Ways to put up to `seeds` seeds in `cups` cups.
+    fn up_to(&self, seeds: usize, cups: usize) -> u64 {
+        self.choose[seeds + cups][cups]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::Ayoayo;
+    use crate::game::Game;
+    use crate::kalah::Kalah;
+    use crate::toguz_korgool::ToguzKorgool;
+
+    #[test]
+    fn round_trip() {
+        let ranking = Ranking::new(4, 6).unwrap();
+        assert_eq!(84, ranking.boards(6));
+        assert_eq!(2 * 210, ranking.positions());
+        for rank in 0..ranking.positions() {
+            let position = ranking.unrank(rank).unwrap();
+            assert_eq!(Some(rank), ranking.rank(&position));
+        }
+        assert_eq!(None, ranking.unrank(ranking.positions()));
+
+        let first = ranking.unrank(2 * ranking.boards_below(3)).unwrap();
+        assert_eq!("0,0/0,3 0/0 0/0 1", first.to_string());
+        let last = ranking.unrank(ranking.positions() - 1).unwrap();
+        assert_eq!("6,0/0,0 0/0 0/0 2", last.to_string());
+    }
+
+    #[test]
+    fn any_ruleset() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        game.play(3)?;
+        let position = game.position().unwrap();
+        let ranking = Ranking::for_rules(&Ayoayo::new(), 48)?;
+        let rank = ranking.rank(&position).unwrap();
+        let unranked = ranking.unrank(rank).unwrap();
+        assert_eq!(position, unranked);
+        assert_eq!(
+            game.board().cups(),
+            unranked.to_game(Ayoayo::new())?.board().cups()
+        );
+        assert_eq!(None, Ranking::new(12, 47)?.rank(&position));
+
+        let kalah = Game::new(Kalah::new(3, 2)?).position().unwrap();
+        let ranking = Ranking::for_rules(&Kalah::new(3, 2)?, 12)?;
+        assert_eq!(6, ranking.cups());
+        assert_eq!(Some(kalah), ranking.unrank(ranking.rank(&kalah).unwrap()));
+        assert_eq!(18, Ranking::for_rules(&ToguzKorgool::new(), 9)?.cups());
+        Ok(())
+    }
+
+    #[test]
+    fn too_big() {
+        assert_eq!(Err(MancalaError::InvalidBoard), Ranking::new(0, 4));
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            Ranking::new(MAX_CUPS + 1, 4)
+        );
+        assert_eq!(Err(MancalaError::InvalidBoard), Ranking::new(32, 200));
+    }
+}
@@ -0,0 +1,300 @@
+//! Game records for Ayoayo, written like PGN: tag pairs, then the numbered
+//! move list and the result.
+//!
+//! ```text
+//! [Variant "Ayoayo"]
+//! [Player1 "Ada"]
+//! [Player2 "Bayo"]
+//! [Date "2020.05.01"]
+//! [Event "Club night"]
+//! [Result "*"]
+//!
+//! 1. 4 1 2. 1 5 *
+//! ```
+//!
+//! Pits are counted from 1, Player 1 moves first in each pair, the pairs are
+//! numbered from 1 up, and the result is `1-0`, `0-1`, `1/2-1/2` or `*` for
+//! a game still being played.
+
+use crate::ayoayo::Ayoayo;
+use crate::board::Move;
+use crate::game::{Game, Ruleset};
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const VARIANT: &str = "Ayoayo";
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Record {
+    pub player1: String,
+    pub player2: String,
+    pub date: String,
+    pub event: String,
+    /// Pits played, counted from 0 like `Game::play`.
+    pub moves: Vec<usize>,
+    pub result: GameState,
+}
+
+impl Record {
+    /// Records the turns played in `game` so far, with the other tags unknown.
+    /// Fails with `InvalidBoard` unless the game is being played by the
+    /// default rules from the usual opening, the only game a record describes.
+    pub fn from_game(game: &Game<Ayoayo>) -> Result<Record> {
+        let rules = Ayoayo::new();
+        if *game.rules() != rules || game.board().moves().first() != rules.setup().moves().first() {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let mut player = rules.first_player();
+        let mut moves = Vec::new();
+        for turn in game.board().moves().iter().skip(1) {
+            match turn.first() {
+                Some(Move::Pickup(cup)) if cup.owner == player => moves.push(cup.pos),
+                _ => return Err(MancalaError::InvalidBoard),
+            }
+            player = player.next_player();
+        }
+        Ok(Record {
+            player1: String::from("?"),
+            player2: String::from("?"),
+            date: String::from("????.??.??"),
+            event: String::from("?"),
+            moves,
+            result: game.state.clone(),
+        })
+    }
+
+    /// Plays the moves from the start, stopping at the first illegal one.
+    pub fn replay(&self) -> Result<Game<Ayoayo>> {
+        let mut game = Game::new(Ayoayo::new());
+        for cup in self.moves.iter() {
+            if game.legal_moves().is_empty() {
+                return Err(MancalaError::IllegalMove);
+            }
+            game.play(*cup)?;
+        }
+        Ok(game)
+    }
+}
+
+fn result(state: &GameState) -> &'static str {
+    match state {
+        GameState::Won(Player::Player1) => "1-0",
+        GameState::Won(Player::Player2) => "0-1",
+        GameState::Draw => "1/2-1/2",
+        GameState::InProgress(_) | GameState::Simultaneous => "*",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = result(&self.result);
+        for (name, value) in [
+            ("Variant", VARIANT),
+            ("Player1", &self.player1),
+            ("Player2", &self.player2),
+            ("Date", &self.date),
+            ("Event", &self.event),
+            ("Result", result),
+        ]
+        .iter()
+        {
+            writeln!(fmt, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(fmt)?;
+        for (number, turn) in self.moves.chunks(2).enumerate() {
+            write!(fmt, "{}.", number + 1)?;
+            for cup in turn {
+                write!(fmt, " {}", cup + 1)?;
+            }
+            write!(fmt, " ")?;
+        }
+        writeln!(fmt, "{}", result)
+    }
+}
+
+// Reads a `[Name "value"]` line.
+fn tag(line: &str) -> Result<(&str, String)> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or(MancalaError::InvalidNotation)?;
+    let (name, quoted) = inner.split_once(' ').ok_or(MancalaError::InvalidNotation)?;
+    let quoted = quoted
+        .trim()
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+        .ok_or(MancalaError::InvalidNotation)?;
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next().ok_or(MancalaError::InvalidNotation)?),
+            '"' => return Err(MancalaError::InvalidNotation),
+            c => value.push(c),
+        }
+    }
+    Ok((name, value))
+}
+
+impl FromStr for Record {
+    type Err = MancalaError;
+
+    /// Reads a record and replays it, so the moves have to be legal and the
+    /// result has to be where they lead.
+    fn from_str(text: &str) -> Result<Record> {
+        let mut record = Record::from_game(&Game::new(Ayoayo::new()))?;
+        let mut tagged_result = None;
+        let mut lines = text.lines().map(str::trim).peekable();
+        while let Some(line) = lines.next_if(|line| line.is_empty() || line.starts_with('[')) {
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = tag(line)?;
+            match name {
+                "Variant" if value != VARIANT => return Err(MancalaError::InvalidNotation),
+                "Player1" => record.player1 = value,
+                "Player2" => record.player2 = value,
+                "Date" => record.date = value,
+                "Event" => record.event = value,
+                "Result" => tagged_result = Some(value),
+                _ => (),
+            }
+        }
+
+        let mut ended = None;
+        for token in lines.flat_map(str::split_whitespace) {
+            if ended.is_some() {
+                return Err(MancalaError::InvalidNotation);
+            }
+            match token {
+                "1-0" | "0-1" | "1/2-1/2" | "*" => ended = Some(token),
+                // A move number has to come before Player 1's move in its pair.
+                number if number.ends_with('.') => {
+                    let number = number[..number.len() - 1]
+                        .parse::<usize>()
+                        .map_err(|_| MancalaError::InvalidNotation)?;
+                    let played = record.moves.len();
+                    if !played.is_multiple_of(2) || number != played / 2 + 1 {
+                        return Err(MancalaError::InvalidNotation);
+                    }
+                }
+                cup => match cup.parse::<usize>() {
+                    Ok(cup) if cup > 0 => record.moves.push(cup - 1),
+                    _ => return Err(MancalaError::InvalidNotation),
+                },
+            }
+        }
+
+        record.result = record.replay()?.state;
+        let played = result(&record.result);
+        if [ended, tagged_result.as_deref()]
+            .iter()
+            .flatten()
+            .any(|noted| *noted != played)
+        {
+            return Err(MancalaError::InvalidNotation);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ayoayo::AyoayoConfig;
+    use crate::position::Position;
+
+    #[test]
+    fn write_and_read() -> Result<()> {
+        let mut game = Game::new(Ayoayo::new());
+        for cup in [3, 0, 0, 4, 2].iter() {
+            game.play(*cup)?;
+        }
+        let mut record = Record::from_game(&game)?;
+        record.player1 = String::from("Ada \"the Sower\"");
+        record.event = String::from("Club night");
+        let text = record.to_string();
+        assert_eq!(
+            "[Variant \"Ayoayo\"]\n\
+             [Player1 \"Ada \\\"the Sower\\\"\"]\n\
+             [Player2 \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Event \"Club night\"]\n\
+             [Result \"*\"]\n\
+             \n\
+             1. 4 1 2. 1 5 3. 3 *\n",
+            text
+        );
+        let read: Record = text.parse()?;
+        assert_eq!(record, read);
+        assert_eq!(game.board().cups(), read.replay()?.board().cups());
+        assert_eq!(GameState::InProgress(Player::Player2), read.result);
+        Ok(())
+    }
+
+    #[test]
+    fn checks_moves_and_result() {
+        assert_eq!(Ok(vec![3, 0]), "1. 4 1".parse().map(|r: Record| r.moves));
+        assert_eq!(
+            Err(MancalaError::NoSuchCup),
+            "1. 4 1 2. 7".parse::<Record>()
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidNotation),
+            "[Result \"1-0\"]\n\n1. 4 *".parse::<Record>()
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidNotation),
+            "1. 4 1-0".parse::<Record>()
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidNotation),
+            "[Variant \"Oware\"]\n\n1. 4 *".parse::<Record>()
+        );
+        assert_eq!(
+            Err(MancalaError::InvalidNotation),
+            "1. 4 0".parse::<Record>()
+        );
+    }
+
+    #[test]
+    fn only_records_usual_games() -> Result<()> {
+        let config = AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        };
+        let game = Game::new(Ayoayo::with_config(config)?);
+        assert_eq!(Err(MancalaError::InvalidBoard), Record::from_game(&game));
+
+        let position: Position = "0,0,1,2,0,0/1,0,0,0,0,3 20/21 0/0 1".parse()?;
+        let game = position.to_game(Ayoayo::new())?;
+        assert_eq!(Err(MancalaError::InvalidBoard), Record::from_game(&game));
+
+        let mut opening = Game::new(Ayoayo::new()).position().unwrap();
+        opening.to_move = Player::Player2;
+        let mut game = opening.to_game(Ayoayo::new())?;
+        game.play(3)?;
+        assert_eq!(Err(MancalaError::InvalidBoard), Record::from_game(&game));
+        Ok(())
+    }
+
+    #[test]
+    fn checks_move_numbers() {
+        for moves in ["2. 4 1", "1. 4 1 3. 1", "1. 4 2. 1", "1. 4 1 1. 1"].iter() {
+            assert_eq!(
+                Err(MancalaError::InvalidNotation),
+                moves.parse::<Record>(),
+                "{}",
+                moves
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Solves Ayoayo games outright for small boards: every position reachable
+//! from the start is found and kept in a transposition table, then they're
+//! solved a layer of seeds at a time by retrograde analysis, the way
+//! `tablebase` solves every board.
+//!
+//! That makes it an oracle for `ai` and settles who a variant favours. A
+//! game that goes round forever counts as neither side banking any more.
+
+use crate::ayoayo::Ayoayo;
+use crate::game::Game;
+use crate::position::Position;
+use crate::tablebase::{banked, Edge, Layer};
+use crate::{GameState, MancalaError, Player, Result};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    /// How the game ends with both sides playing their best.
+    pub result: GameState,
+    /// Player 1's bank less Player 2's at the end.
+    pub margin: i32,
+    /// The cups both sides play, from the start until the game ends or
+    /// comes back to a position it's been in.
+    pub line: Vec<usize>,
+    /// How many positions can be reached.
+    pub positions: usize,
+}
+
+/// Solves the game `rules` sets up.
+pub fn solve(rules: &Ayoayo) -> Result<Solution> {
+    let position = Game::new(*rules)
+        .position()
+        .ok_or(MancalaError::InvalidBoard)?;
+    solve_position(rules, &position)
+}
+
+/// Solves `position`, which needs the cups `rules` sets up and no seeds in
+/// hand.
+pub fn solve_position(rules: &Ayoayo, position: &Position) -> Result<Solution> {
+    if position.seeds().len() != 2 * rules.config().pits || position.in_hand != [0, 0] {
+        return Err(MancalaError::InvalidBoard);
+    }
+    let mut graph = Graph::explore(rules, key(position));
+    let values = graph.solve();
+
+    let margin = banked(position) + values[0];
+    let mut line = Vec::new();
+    let mut seen = HashSet::new();
+    let mut node = 0;
+    while seen.insert(node) {
+        let cup = match graph.best[node] {
+            Some(cup) => cup,
+            None => break,
+        };
+        line.push(cup);
+        node = graph.edges[node]
+            .iter()
+            .find(|(played, _, _)| *played == cup)
+            .map(|(_, child, _)| *child)
+            .expect("the best move is one of the moves");
+    }
+    Ok(Solution {
+        result: match margin.cmp(&0) {
+            Ordering::Greater => GameState::Won(Player::Player1),
+            Ordering::Less => GameState::Won(Player::Player2),
+            Ordering::Equal => GameState::Draw,
+        },
+        margin,
+        line,
+        positions: graph.positions.len(),
+    })
+}
+
+// Banks don't change how a position should be played, so positions that
+// only differ in them share an entry.
+fn key(position: &Position) -> Position {
+    let mut key = *position;
+    key.banks = [0, 0];
+    key
+}
+
+fn seeds(position: &Position) -> usize {
+    position.seeds().iter().map(|seeds| *seeds as usize).sum()
+}
+
+struct Graph {
+    // Every position reachable from the first, which is where it starts.
+    positions: Vec<Position>,
+    // The cup, the position it leads to and what it banks for Player 1.
+    edges: Vec<Vec<(usize, usize, i32)>>,
+    best: Vec<Option<usize>>,
+}
+
+impl Graph {
+    fn explore(rules: &Ayoayo, start: Position) -> Graph {
+        let mut table = HashMap::new();
+        let mut positions = vec![start];
+        let mut edges = Vec::new();
+        let mut queue = VecDeque::new();
+        table.insert(start, 0);
+        queue.push_back(0);
+        while let Some(node) = queue.pop_front() {
+            let position = positions[node];
+            let mut moves = Vec::new();
+            for cup in 0..rules.config().pits {
+                let played = match rules.play_move(&position, cup) {
+                    Ok(played) => played,
+                    Err(_) => continue,
+                };
+                let after = key(&played);
+                let child = *table.entry(after).or_insert_with(|| {
+                    positions.push(after);
+                    queue.push_back(positions.len() - 1);
+                    positions.len() - 1
+                });
+                moves.push((cup, child, banked(&played)));
+            }
+            edges.push(moves);
+        }
+        let best = vec![None; positions.len()];
+        Graph {
+            positions,
+            edges,
+            best,
+        }
+    }
+
+    // Works out each position's value for Player 1, fewest seeds first,
+    // since seeds only ever leave the board.
+    fn solve(&mut self) -> Vec<i32> {
+        let mut layers = BTreeMap::new();
+        for (node, position) in self.positions.iter().enumerate() {
+            layers
+                .entry(seeds(position))
+                .or_insert_with(Vec::new)
+                .push(node);
+        }
+
+        let mut values = vec![0; self.positions.len()];
+        let mut local = vec![0; self.positions.len()];
+        for (seeds, nodes) in layers {
+            for (index, node) in nodes.iter().enumerate() {
+                local[*node] = index;
+            }
+            let moves = nodes
+                .iter()
+                .map(|node| {
+                    self.edges[*node]
+                        .iter()
+                        .map(|(cup, child, gained)| {
+                            let edge = if self::seeds(&self.positions[*child]) == seeds {
+                                Edge::Same(local[*child] as u32)
+                            } else {
+                                Edge::Exit(gained + values[*child])
+                            };
+                            (*cup as u8, edge)
+                        })
+                        .collect()
+                })
+                .collect();
+            let movers = nodes
+                .iter()
+                .map(|node| self.positions[*node].to_move)
+                .collect();
+            let (solved, best) = Layer::new(moves, movers).solve(seeds);
+            for (index, node) in nodes.iter().enumerate() {
+                values[*node] = solved[index];
+                self.best[*node] = best[index].map(usize::from);
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{self, SearchConfig};
+    use crate::ayoayo::{AyoayoConfig, Leftovers};
+    use crate::tablebase::Tablebase;
+
+    fn rules(pits: usize, seeds: usize) -> Ayoayo {
+        Ayoayo::with_config(AyoayoConfig {
+            pits,
+            seeds,
+            ..AyoayoConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn agrees_with_tablebase() -> Result<()> {
+        for (pits, seeds) in [(2, 1), (2, 2), (2, 3), (3, 1)].iter() {
+            for leftovers in [Leftovers::Mover, Leftovers::Opponent, Leftovers::Nobody].iter() {
+                let rules = Ayoayo::with_config(AyoayoConfig {
+                    leftovers: *leftovers,
+                    ..*rules(*pits, *seeds).config()
+                })?;
+                let solution = solve(&rules)?;
+                let table = Tablebase::generate(rules, 2 * pits * seeds)?;
+                let start = Game::new(rules).position().unwrap();
+                let probe = table.probe(&start).unwrap();
+                assert_eq!(probe.margin, solution.margin, "{:?}", rules);
+                assert_eq!(probe.result, solution.result, "{:?}", rules);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn small_boards() -> Result<()> {
+        let solved = |pits, seeds| solve(&rules(pits, seeds)).map(|s| (s.result, s.margin));
+        assert_eq!(Ok((GameState::Draw, 0)), solved(2, 1));
+        assert_eq!(Ok((GameState::Won(Player::Player2), -8)), solved(2, 3));
+        assert_eq!(Ok((GameState::Won(Player::Player1), 6)), solved(3, 1));
+        assert_eq!(Ok(vec![0]), solve(&rules(3, 1)).map(|s| s.line));
+        Ok(())
+    }
+
+    #[test]
+    fn line_plays_out() -> Result<()> {
+        for (pits, seeds) in [(2, 2), (3, 2), (4, 1)].iter() {
+            let rules = rules(*pits, *seeds);
+            let solution = solve(&rules)?;
+            let mut game = Game::new(rules);
+            for cup in solution.line.iter() {
+                game.play(*cup)?;
+            }
+            // Unless it went round, and the banks are where they'll stay.
+            if !matches!(game.state, GameState::InProgress(_)) {
+                assert_eq!(solution.result, game.state);
+            }
+            let banks =
+                game.get_bank(Player::Player1) as i32 - game.get_bank(Player::Player2) as i32;
+            assert_eq!(solution.margin, banks, "{:?}", rules);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn agrees_with_search() -> Result<()> {
+        let rules = rules(3, 1);
+        let solution = solve(&rules)?;
+        let start = Game::new(rules).position().unwrap();
+        let config = SearchConfig {
+            depth: 30,
+            time: None,
+        };
+        let result = ai::search(&rules, &start, &config)?.unwrap();
+        assert!(result.score.abs() > ai::WIN - 30);
+        let winner = if result.score > 0 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        assert_eq!(GameState::Won(winner), solution.result);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_position() -> Result<()> {
+        let position: Position = "1,0/0,1 0/0 0/0 1".parse()?;
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            solve_position(&rules(3, 1), &position)
+        );
+        let holding: Position = "1,0,0/0,1,0 0/0 1/0 1".parse()?;
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            solve_position(&rules(3, 1), &holding)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,542 @@
+//! Endgame tables for Ayoayo: every position with up to a given number of
+//! seeds on the board, solved exactly by retrograde analysis with
+//! `Ayoayo::play_move`, so relay sowing, must-feed and who collects the
+//! leftovers all follow the rules the table was built for.
+//!
+//! Banks don't change how a position should be played, so the table keeps
+//! the seeds the side to move can still win over their opponent from each
+//! board. A game that goes round forever counts as neither side winning any
+//! more.
+//!
+//! Only Ayoayo can play moves on a `Position`, so it's the only variant with
+//! tables for now.
+
+use crate::ayoayo::{Ayoayo, AyoayoConfig, Leftovers};
+use crate::position::Position;
+use crate::ranking::Ranking;
+use crate::{GameState, MancalaError, Player, Result};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"AYTB";
+const VERSION: u8 = 2;
+const NO_MOVE: u8 = u8::MAX;
+
+/// What the table knows about a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probe {
+    /// How the game ends with both sides playing their best.
+    pub result: GameState,
+    /// The side to move's bank less their opponent's at the end.
+    pub margin: i32,
+    /// A cup that gets `result` and `margin`, or None when the game is over.
+    pub best: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tablebase {
+    rules: Ayoayo,
+    max_seeds: usize,
+    ranking: Ranking,
+    // A value and a best move for each position, by `Ranking::rank`.
+    values: Vec<i8>,
+    best: Vec<u8>,
+}
+
+impl Tablebase {
+    /// Solves every position of `rules` with at most `max_seeds` on the
+    /// board. Fails with `InvalidBoard` if there are more cups than a
+    /// `Position` holds, more seeds than the table can count or more
+    /// positions than it can number.
+    pub fn generate(rules: Ayoayo, max_seeds: usize) -> Result<Tablebase> {
+        if max_seeds > i8::MAX as usize {
+            return Err(MancalaError::InvalidBoard);
+        }
+        let ranking = Ranking::new(2 * rules.config().pits, max_seeds)?;
+        let entries = entries(&ranking).ok_or(MancalaError::InvalidBoard)?;
+        let mut table = Tablebase {
+            rules,
+            max_seeds,
+            ranking,
+            values: vec![0; entries],
+            best: vec![NO_MOVE; entries],
+        };
+        // Seeds only ever leave the board, so each layer only leads to
+        // itself and the ones already solved.
+        for seeds in 0..=max_seeds {
+            table.solve_layer(seeds);
+        }
+        Ok(table)
+    }
+
+    pub fn rules(&self) -> &Ayoayo {
+        &self.rules
+    }
+
+    /// The most seeds on the board of any position in the table.
+    pub fn max_seeds(&self) -> usize {
+        self.max_seeds
+    }
+
+    /// Looks up `position`, or None if it isn't in the table: it has too
+    /// many seeds, the wrong number of cups, or seeds in hand.
+    pub fn probe(&self, position: &Position) -> Option<Probe> {
+        if position.in_hand != [0, 0] {
+            return None;
+        }
+        let index = self.ranking.rank(position)? as usize;
+        let player = position.to_move;
+        let margin = position.bank(player) as i32 - position.bank(player.next_player()) as i32
+            + self.values[index] as i32;
+        Some(Probe {
+            result: match margin.cmp(&0) {
+                Ordering::Greater => GameState::Won(player),
+                Ordering::Less => GameState::Won(player.next_player()),
+                Ordering::Equal => GameState::Draw,
+            },
+            margin,
+            best: match self.best[index] {
+                NO_MOVE => None,
+                cup => Some(cup as usize),
+            },
+        })
+    }
+
+    /// Writes the table out: a short header with the rules it was built for,
+    /// then two bytes for each position.
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let config = self.rules.config();
+        let leftovers = match config.leftovers {
+            Leftovers::Mover => 0,
+            Leftovers::Opponent => 1,
+            Leftovers::Nobody => 2,
+        };
+        // `with_config` keeps every seed countable in a u16.
+        let seeds = (config.seeds as u16).to_le_bytes();
+        out.write_all(MAGIC)?;
+        out.write_all(&[
+            VERSION,
+            config.pits as u8,
+            seeds[0],
+            seeds[1],
+            config.must_feed as u8,
+            leftovers,
+            self.max_seeds as u8,
+        ])?;
+        for (value, best) in self.values.iter().zip(self.best.iter()) {
+            out.write_all(&[*value as u8, *best])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a table written by `write`.
+    pub fn read<R: Read>(mut input: R) -> io::Result<Tablebase> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not an Ayoayo endgame table");
+        let mut header = [0; 11];
+        input.read_exact(&mut header)?;
+        let [pits, must_feed, leftovers, max_seeds] = [header[5], header[8], header[9], header[10]];
+        let seeds = u16::from_le_bytes([header[6], header[7]]);
+        if header[..4] != MAGIC[..] || header[4] != VERSION || max_seeds > i8::MAX as u8 {
+            return Err(invalid());
+        }
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: pits as usize,
+            seeds: seeds as usize,
+            must_feed: must_feed != 0,
+            leftovers: match leftovers {
+                0 => Leftovers::Mover,
+                1 => Leftovers::Opponent,
+                2 => Leftovers::Nobody,
+                _ => return Err(invalid()),
+            },
+        })
+        .map_err(|_| invalid())?;
+        let max_seeds = max_seeds as usize;
+        let ranking = Ranking::new(2 * pits as usize, max_seeds).map_err(|_| invalid())?;
+        let size = entries(&ranking)
+            .and_then(|entries| entries.checked_mul(2))
+            .ok_or_else(invalid)?;
+        // Reading one byte past the end is enough to tell the table is too
+        // long, and a header can't ask for more memory than the input has.
+        let mut bytes = Vec::new();
+        input.take(size as u64 + 1).read_to_end(&mut bytes)?;
+        if bytes.len() != size {
+            return Err(invalid());
+        }
+        Ok(Tablebase {
+            rules,
+            max_seeds,
+            ranking,
+            values: bytes.iter().step_by(2).map(|value| *value as i8).collect(),
+            best: bytes.iter().skip(1).step_by(2).copied().collect(),
+        })
+    }
+
+    // The value of a solved position for Player 1.
+    fn player1_value(&self, position: &Position) -> i32 {
+        let rank = self
+            .ranking
+            .rank(position)
+            .expect("seeds only leave the board");
+        let value = self.values[rank as usize] as i32;
+        match position.to_move {
+            Player::Player1 => value,
+            Player::Player2 => -value,
+        }
+    }
+
+    fn solve_layer(&mut self, seeds: usize) {
+        let layer = self.layer(seeds);
+        let first = 2 * self.ranking.boards_below(seeds) as usize;
+        let (values, best) = layer.solve(seeds);
+        for (node, (value, best)) in values.into_iter().zip(best).enumerate() {
+            self.values[first + node] = match layer.movers[node] {
+                Player::Player1 => value,
+                Player::Player2 => -value,
+            } as i8;
+            self.best[first + node] = best.unwrap_or(NO_MOVE);
+        }
+    }
+
+    // Every board with `seeds` on it, with Player 1 and then Player 2 to move.
+    fn layer(&self, seeds: usize) -> Layer {
+        let rules = &self.rules;
+        let boards = self.ranking.boards(seeds);
+        let mut moves = Vec::with_capacity(2 * boards as usize);
+        let mut movers = Vec::with_capacity(2 * boards as usize);
+        for rank in 0..boards {
+            let board = self.ranking.unrank_board(rank, seeds);
+            for to_move in [Player::Player1, Player::Player2].iter() {
+                let position = Position::new(&board, [0, 0], [0, 0], *to_move)
+                    .expect("the table checked the size");
+                let mut edges = Vec::new();
+                for cup in 0..rules.config().pits {
+                    let after = match rules.play_move(&position, cup) {
+                        Ok(after) => after,
+                        Err(_) => continue,
+                    };
+                    let left: usize = after.seeds().iter().map(|seeds| *seeds as usize).sum();
+                    let edge = if left == seeds {
+                        Edge::Same(
+                            (2 * self.ranking.rank_board(after.seeds()) as usize
+                                + side(after.to_move)) as u32,
+                        )
+                    } else {
+                        Edge::Exit(banked(&after) + self.player1_value(&after))
+                    };
+                    edges.push((cup as u8, edge));
+                }
+                moves.push(edges);
+                movers.push(*to_move);
+            }
+        }
+        Layer::new(moves, movers)
+    }
+}
+
+/// Player 1's bank less Player 2's.
+pub(crate) fn banked(position: &Position) -> i32 {
+    position.bank(Player::Player1) as i32 - position.bank(Player::Player2) as i32
+}
+
+// How many positions `ranking` numbers, if a table can hold them all: a
+// layer's positions are numbered with a `u32`.
+fn entries(ranking: &Ranking) -> Option<usize> {
+    let positions = ranking.positions();
+    if positions > u64::from(u32::MAX) {
+        return None;
+    }
+    usize::try_from(positions).ok()
+}
+
+fn side(player: Player) -> usize {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => 1,
+    }
+}
+
+pub(crate) enum Edge {
+    // To another position in the same layer.
+    Same(u32),
+    // Out of the layer, worth this much to Player 1 all told.
+    Exit(i32),
+}
+
+/// Positions with the same number of seeds on the board, with the moves
+/// between them, numbered from 0.
+pub(crate) struct Layer {
+    moves: Vec<Vec<(u8, Edge)>>,
+    movers: Vec<Player>,
+    // The positions that can move to each one, and with which cup.
+    parents: Vec<Vec<(u32, u8)>>,
+}
+
+impl Layer {
+    pub(crate) fn new(moves: Vec<Vec<(u8, Edge)>>, movers: Vec<Player>) -> Layer {
+        let mut parents = vec![Vec::new(); moves.len()];
+        for (node, edges) in moves.iter().enumerate() {
+            for (cup, edge) in edges.iter() {
+                if let Edge::Same(child) = edge {
+                    parents[*child as usize].push((node as u32, *cup));
+                }
+            }
+        }
+        Layer {
+            moves,
+            movers,
+            parents,
+        }
+    }
+
+    /// Solves every position, given that the most either player can win
+    /// from one is `seeds`. Returns each one's value for Player 1 and a cup
+    /// the side to move can get it with.
+    pub(crate) fn solve(&self, seeds: usize) -> (Vec<i32>, Vec<Option<u8>>) {
+        let mut values: Vec<Option<i32>> = vec![None; self.moves.len()];
+        let mut best = vec![None; self.moves.len()];
+
+        // Player 1 gets at least `target` from the positions they can force
+        // there, best targets first...
+        for target in (1..=seeds as i32).rev() {
+            let (won, triggers) = self.attract(Player::Player1, |value| value >= target);
+            for node in 0..values.len() {
+                if !won[node] || values[node].is_some() {
+                    continue;
+                }
+                values[node] = Some(target);
+                if self.movers[node] == Player::Player1 {
+                    best[node] = triggers[node];
+                }
+            }
+        }
+        // ...and Player 2 keeps them below it from the ones they can force.
+        for target in 1 - seeds as i32..=0 {
+            let (won, triggers) = self.attract(Player::Player2, |value| value < target);
+            for node in 0..values.len() {
+                if !won[node] || values[node].is_some() {
+                    continue;
+                }
+                values[node] = Some(target - 1);
+                if self.movers[node] == Player::Player2 {
+                    best[node] = triggers[node];
+                }
+            }
+        }
+        // Anywhere else both can keep the game going for ever.
+        let values: Vec<i32> = values.into_iter().map(Option::unwrap_or_default).collect();
+
+        for (node, moves) in self.moves.iter().enumerate() {
+            let value = values[node];
+            // Without a forced line, any move that holds the value will do.
+            best[node] = best[node].or_else(|| {
+                moves.iter().find_map(|(cup, edge)| {
+                    let after = match edge {
+                        Edge::Exit(value) => *value,
+                        Edge::Same(child) => values[*child as usize],
+                    };
+                    let holds = match self.movers[node] {
+                        Player::Player1 => after >= value,
+                        Player::Player2 => after <= value,
+                    };
+                    if holds {
+                        Some(*cup)
+                    } else {
+                        None
+                    }
+                })
+            });
+        }
+        (values, best)
+    }
+
+    // The positions from which `player` can make sure the game leaves the
+    // layer with a value that meets `target`, along with the cup that does
+    // it where `player` is to move. The game ending counts as a value of 0.
+    fn attract(
+        &self,
+        player: Player,
+        target: impl Fn(i32) -> bool,
+    ) -> (Vec<bool>, Vec<Option<u8>>) {
+        let mut won = vec![false; self.moves.len()];
+        let mut triggers = vec![None; self.moves.len()];
+        // Moves the opponent has left that stay out of reach, or None once
+        // one of them leaves the layer safely.
+        let mut left: Vec<Option<usize>> = vec![None; self.moves.len()];
+        let mut queue = VecDeque::new();
+        for (node, moves) in self.moves.iter().enumerate() {
+            let exits = moves.iter().filter_map(|(cup, edge)| match edge {
+                Edge::Exit(value) => Some((*cup, *value)),
+                Edge::Same(_) => None,
+            });
+            if moves.is_empty() {
+                won[node] = target(0);
+            } else if self.movers[node] == player {
+                triggers[node] = exits
+                    .filter(|(_, value)| target(*value))
+                    .map(|(cup, _)| cup)
+                    .next();
+                won[node] = triggers[node].is_some();
+            } else if exits.clone().all(|(_, value)| target(value)) {
+                let count = moves.len() - exits.count();
+                won[node] = count == 0;
+                left[node] = Some(count);
+            }
+            if won[node] {
+                queue.push_back(node);
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            for (parent, cup) in self.parents[node].iter() {
+                let parent = *parent as usize;
+                if won[parent] {
+                    continue;
+                }
+                if self.movers[parent] == player {
+                    won[parent] = true;
+                    triggers[parent] = Some(*cup);
+                } else if let Some(count) = left[parent].as_mut() {
+                    *count -= 1;
+                    won[parent] = *count == 0;
+                }
+                if won[parent] {
+                    queue.push_back(parent);
+                }
+            }
+        }
+        (won, triggers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{self, SearchConfig};
+
+    fn three_pits() -> Ayoayo {
+        Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            ..AyoayoConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn probe() -> Result<()> {
+        let table = Tablebase::generate(three_pits(), 6)?;
+        // Sowing the first cup captures all of Player 2's seeds and ends the game.
+        let position: Position = "1,0,1/0,3,0 5/4 0/0 1".parse()?;
+        assert_eq!(
+            Some(Probe {
+                result: GameState::Won(Player::Player1),
+                margin: 6,
+                best: Some(0),
+            }),
+            table.probe(&position)
+        );
+
+        let over: Position = "0,0,0/2,0,0 5/4 0/0 1".parse()?;
+        assert_eq!(Some(None), table.probe(&over).map(|probe| probe.best));
+        assert_eq!(Some(1), table.probe(&over).map(|probe| probe.margin));
+
+        let too_many: Position = "4,0,0/0,3,0 5/4 0/0 1".parse()?;
+        assert_eq!(None, table.probe(&too_many));
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            Tablebase::generate(three_pits(), 200).map(|_| ())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn best_moves_keep_the_margin() -> Result<()> {
+        let rules = three_pits();
+        let table = Tablebase::generate(rules, 7)?;
+        for rank in 0..table.ranking.boards(7) {
+            let board = table.ranking.unrank_board(rank, 7);
+            let mut position = Position::new(&board, [0, 0], [0, 0], Player::Player1)?;
+            let mut probe = table.probe(&position).unwrap();
+            // Played out, the best moves end where the table said, or go
+            // round for ever without anyone banking anything.
+            for _ in 0..100 {
+                let cup = match probe.best {
+                    Some(cup) => cup,
+                    None => break,
+                };
+                position = rules.play_move(&position, cup)?;
+                let next = table.probe(&position).unwrap();
+                assert_eq!(-probe.margin, next.margin, "{}", position);
+                probe = next;
+            }
+            if probe.best.is_none() {
+                let player = position.to_move;
+                let banked =
+                    position.bank(player) as i32 - position.bank(player.next_player()) as i32;
+                assert_eq!(banked, probe.margin, "{}", position);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn agrees_with_search() -> Result<()> {
+        let rules = three_pits();
+        let table = Tablebase::generate(rules, 5)?;
+        for notation in ["0,2,1/1,0,1 5/4 0/0 2", "1,0,0/2,0,2 5/4 0/0 2"].iter() {
+            let position: Position = notation.parse()?;
+            let probe = table.probe(&position).unwrap();
+            let config = SearchConfig {
+                depth: 20,
+                time: None,
+            };
+            let result = ai::search(&rules, &position, &config)?.unwrap();
+            assert!(result.score.abs() > ai::WIN - 20, "{}", notation);
+            let winner = if result.score > 0 {
+                position.to_move
+            } else {
+                position.to_move.next_player()
+            };
+            assert_eq!(GameState::Won(winner), probe.result, "{}", notation);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn write_and_read() -> Result<()> {
+        let rules = Ayoayo::with_config(AyoayoConfig {
+            pits: 3,
+            must_feed: false,
+            leftovers: Leftovers::Opponent,
+            seeds: 300,
+        })?;
+        let table = Tablebase::generate(rules, 4)?;
+        let mut bytes = Vec::new();
+        table.write(&mut bytes).unwrap();
+        assert_eq!(11 + 2 * 2 * 210, bytes.len());
+        let read = Tablebase::read(&bytes[..]).unwrap();
+        assert_eq!(rules, *read.rules());
+        assert_eq!(table, read);
+        // Rules `with_config` turns down aren't read either.
+        let mut no_seeds = bytes.clone();
+        no_seeds[6..8].copy_from_slice(&[0, 0]);
+        assert!(Tablebase::read(&no_seeds[..]).is_err());
+
+        assert!(Tablebase::read(&bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(Tablebase::read(&bytes[..]).is_err());
+        bytes[0] = b'X';
+        assert!(Tablebase::read(&bytes[..]).is_err());
+
+        // Twelve cups with up to 127 seeds is more than a table can hold.
+        let header = [&MAGIC[..], &[VERSION, 6, 4, 0, 1, 0, 127]].concat();
+        assert!(Tablebase::read(&header[..]).is_err());
+        assert_eq!(
+            Err(MancalaError::InvalidBoard),
+            Tablebase::generate(Ayoayo::new(), 127)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+//! Toguz Korgool: nine pits a side with nine stones in each.
+//!
+//! Sowing starts by dropping a stone back into the pit it was picked up
+//! from, unless that pit only held the one stone. Ending in an opponent's pit
+//! with an even number of stones captures them. Ending there with exactly
+//! three can instead turn the pit into a tuzdyk, which feeds the player's
+//! store for the rest of the game. A player only gets one tuzdyk, it can't be
+//! the opponent's ninth pit, and it can't have the same number as the
+//! opponent's tuzdyk.
+
+use crate::board::{Cup, CupPos, MancalaBoard, Pit};
+use crate::game::{end_game, Ruleset};
+use crate::topology::Direction;
+use crate::{GameState, MancalaError, Player, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const PITS: usize = 9;
+const STARTING_COUNT: usize = 9;
+const WINNING_COUNT: usize = 82;
+const TUZDYK_COUNT: usize = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ToguzKorgool;
+
+impl ToguzKorgool {
+    pub fn new() -> ToguzKorgool {
+        ToguzKorgool
+    }
+
+    fn can_claim_tuzdyk(&self, board: &MancalaBoard, player: Player, cup: CupPos) -> bool {
+        let has_tuzdyk = (0..PITS).any(|pos| {
+            board.tuzdyk(CupPos {
+                owner: cup.owner,
+                pos,
+            }) == Some(player)
+        });
+        let mirrors_opponent = board
+            .tuzdyk(CupPos {
+                owner: player,
+                pos: cup.pos,
+            })
+            .is_some();
+        cup.pos != PITS - 1 && !has_tuzdyk && !mirrors_opponent
+    }
+}
+
+impl Ruleset for ToguzKorgool {
+    type Move = usize;
+
+    fn setup(&self) -> MancalaBoard {
+        let board: Vec<Cup> = [Player::Player1, Player::Player2]
+            .iter()
+            .flat_map(|player| {
+                (0..PITS).map(move |i| Cup {
+                    owner: *player,
+                    seeds: STARTING_COUNT,
+                    pos: i,
+                })
+            })
+            .collect();
+
+        MancalaBoard::new(board).expect("a Toguz Korgool board fits")
+    }
+
+    fn validate(&self, board: &MancalaBoard, player: Player, cup: usize) -> Result<()> {
+        match board.get_cup(CupPos {
+            owner: player,
+            pos: cup,
+        }) {
+            Some(Cup { seeds: 0, .. }) => Err(MancalaError::NoSeedsToSow),
+            None => Err(MancalaError::NoSuchCup),
+            _ => Ok(()),
+        }
+    }
+
+    fn legal_moves(&self, board: &MancalaBoard, player: Player) -> Vec<usize> {
+        (0..PITS)
+            .filter(|cup| self.validate(board, player, *cup).is_ok())
+            .collect()
+    }
+
+    fn sow(&self, board: &mut MancalaBoard, player: Player, cup: usize) -> Result<Pit> {
+        let cup = CupPos {
+            owner: player,
+            pos: cup,
+        };
+        let seeds = board.seeds(Pit::Cup(cup));
+        board.pickup(cup, player);
+        if seeds == 1 {
+            board.sow(player, cup, |_, _, _| true)
+        } else {
+            board.sow_into(player, cup, Direction::Clockwise)
+        }
+    }
+
+    fn capture(&self, board: &mut MancalaBoard, player: Player, last: Pit) {
+        let last = match last {
+            Pit::Cup(last) if last.owner != player && board.tuzdyk(last).is_none() => last,
+            _ => return,
+        };
+        let seeds = board.seeds(Pit::Cup(last));
+        if seeds.is_multiple_of(2) {
+            board.pickup(last, player);
+            board.bank(player);
+        } else if seeds == TUZDYK_COUNT && self.can_claim_tuzdyk(board, player, last) {
+            board.pickup(last, player);
+            board.bank(player);
+            board.add_tuzdyk(last, player);
+        }
+    }
+
+    fn end_of_turn(&self, board: &mut MancalaBoard, player: Player, _last: Pit) -> GameState {
+        let opponent = player.next_player();
+        // Tuzdyks mean sowing can fill either store.
+        if board.get_bank(player) >= WINNING_COUNT {
+            GameState::Won(player)
+        } else if board.get_bank(opponent) >= WINNING_COUNT {
+            GameState::Won(opponent)
+        } else if board.get_bank(player) == WINNING_COUNT - 1
+            && board.get_bank(opponent) == WINNING_COUNT - 1
+        {
+            GameState::Draw
+        } else if board.starving(opponent) {
+            // `player` keeps whatever is left on their side.
+            end_game(board, Some(player))
+        } else {
+            GameState::InProgress(opponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Move;
+    use crate::game::Game;
+
+    #[test]
+    fn sow_from_origin() -> Result<()> {
+        let mut game = Game::new(ToguzKorgool::new());
+        game.play(0)?;
+        assert_eq!(
+            "0 - ①|⑩|⑩|⑩|⑩|⑩|⑩|⑩|⑩\n⑨|⑨|⑨|⑨|⑨|⑨|⑨|⑨|⑨ - 0",
+            format!("{}", game)
+        );
+
+        let mut game = Game::new(ToguzKorgool::new());
+        game.play(8)?;
+        assert_eq!(
+            "10 - ⑨|⑨|⑨|⑨|⑨|⑨|⑨|⑨|①\n⑩|⑩|⑩|⑩|⑩|⑩|⑩|⓪|⑨ - 0",
+            format!("{}", game)
+        );
+
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(&[1, 0, 0, 0, 0, 0, 0, 0, 0], &[1; 9]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(0)?;
+        assert_eq!(
+            "0 - ⓪|①|⓪|⓪|⓪|⓪|⓪|⓪|⓪\n①|①|①|①|①|①|①|①|① - 0",
+            format!("{}", game)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tuzdyk_feeds_store() -> Result<()> {
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(
+                &[0, 0, 0, 0, 0, 0, 0, 5, 4],
+                &[1, 1, 2, 0, 0, 0, 0, 0, 5],
+            ))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.play(8)?;
+        assert_eq!(
+            "3 - ⓪|⓪|⓪|⓪|⓪|⓪|⓪|⑤|①\n②|②|⓪|⓪|⓪|⓪|⓪|⓪|⑤ - 0",
+            format!("{}", game)
+        );
+        let tuzdyk = CupPos {
+            owner: Player::Player2,
+            pos: 2,
+        };
+        assert_eq!(Some(Player::Player1), game.board().tuzdyk(tuzdyk));
+
+        game.play(8)?;
+        game.play(7)?;
+        assert_eq!(
+            "4 - ①|①|①|①|⓪|⓪|⓪|①|②\n③|③|⓪|⓪|⓪|⓪|⓪|⓪|① - 0",
+            format!("{}", game)
+        );
+        assert_eq!(
+            Some(&Move::Bank(Player::Player1, 1)),
+            game.board().moves.last().unwrap().last()
+        );
+        assert_eq!(game.state, GameState::InProgress(Player::Player2));
+
+        game.undo();
+        game.undo();
+        game.undo();
+        assert_eq!(None, game.board().tuzdyk(tuzdyk));
+        assert_eq!(
+            "0 - ⓪|⓪|⓪|⓪|⓪|⓪|⓪|⑤|④\n①|①|②|⓪|⓪|⓪|⓪|⓪|⑤ - 0",
+            format!("{}", game)
+        );
+        game.redo();
+        assert_eq!(Some(Player::Player1), game.board().tuzdyk(tuzdyk));
+        Ok(())
+    }
+
+    #[test]
+    fn tuzdyk_restrictions() -> Result<()> {
+        // Only one tuzdyk each.
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(
+                &[0, 0, 0, 0, 0, 0, 0, 0, 2],
+                &[2, 0, 0, 0, 0, 0, 0, 0, 0],
+            ))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.board.add_tuzdyk(
+            CupPos {
+                owner: Player::Player2,
+                pos: 5,
+            },
+            Player::Player1,
+        );
+        game.play(8)?;
+        assert_eq!(
+            "0 - ⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪|①\n③|⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪ - 0",
+            format!("{}", game)
+        );
+
+        // Not with the same number as the opponent's.
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(
+                &[0, 0, 0, 0, 0, 0, 0, 0, 2],
+                &[2, 0, 0, 0, 0, 0, 0, 0, 0],
+            ))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.board.add_tuzdyk(
+            CupPos {
+                owner: Player::Player1,
+                pos: 0,
+            },
+            Player::Player2,
+        );
+        game.play(8)?;
+        assert_eq!(
+            None,
+            game.board().tuzdyk(CupPos {
+                owner: Player::Player2,
+                pos: 0,
+            })
+        );
+
+        // Never in the ninth pit.
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(
+                &[1, 1, 1, 1, 1, 1, 1, 1, 2],
+                &[0, 0, 0, 0, 0, 0, 0, 0, 10],
+            ))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.state = GameState::InProgress(Player::Player2);
+        game.play(8)?;
+        assert_eq!(
+            "0 - ②|②|②|②|②|②|②|②|③\n⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪|① - 0",
+            format!("{}", game)
+        );
+        assert_eq!(game.state, GameState::InProgress(Player::Player1));
+        Ok(())
+    }
+
+    #[test]
+    fn starving_opponent_ends_game() -> Result<()> {
+        let mut game = Game::with_board(
+            ToguzKorgool::new(),
+            MancalaBoard::new(Cup::rows(&[1, 1, 0, 0, 0, 0, 0, 0, 0], &[0; 9]))?,
+            GameState::InProgress(Player::Player1),
+        );
+        game.board.deposit(Player::Player1, 70);
+        game.board.deposit(Player::Player2, 80);
+        game.play(0)?;
+        assert_eq!(
+            "72 - ⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪\n⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪|⓪ - 80",
+            format!("{}", game)
+        );
+        assert_eq!(game.state, GameState::Won(Player::Player2));
+        Ok(())
+    }
+}
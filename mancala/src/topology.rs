@@ -0,0 +1,183 @@
+use crate::board::{CupPos, Pit};
+use crate::Player;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Which way seeds travel around a sowing loop. `Clockwise` follows the
+/// loop in the order cups are numbered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Clockwise,
+    Anticlockwise,
+}
+
+/// How the cups on a board are laid out and which way seeds travel around them.
+///
+/// Cups are numbered per player in the order that player sows them. On a
+/// four row board a player's inner row is `0..pits` and their outer row is
+/// `pits..2 * pits`, so their loop runs along the inner row and back down the
+/// outer one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Topology {
+    /// Each player owns one row of `pits` cups and both sow around the same
+    /// ring. With `stores`, each player's store sits after their row.
+    TwoRow { pits: usize, stores: bool },
+    /// Each player owns an inner and an outer row of `pits` cups, and only
+    /// sows around their own two rows.
+    FourRow { pits: usize },
+}
+
+impl Topology {
+    /// Number of cups each player owns.
+    pub fn cups_per_player(&self) -> usize {
+        match *self {
+            Topology::TwoRow { pits, .. } => pits,
+            Topology::FourRow { pits } => 2 * pits,
+        }
+    }
+
+    /// The cups a player owns, in the order they sow them.
+    pub fn cups(&self, player: Player) -> impl Iterator<Item = CupPos> {
+        (0..self.cups_per_player()).map(move |pos| CupPos { owner: player, pos })
+    }
+
+    /// Whether `cup` is in the row that faces the opponent.
+    pub fn is_inner(&self, cup: CupPos) -> bool {
+        match *self {
+            Topology::TwoRow { pits, .. } => cup.pos < pits,
+            Topology::FourRow { pits } => cup.pos < pits,
+        }
+    }
+
+    /// The opponent's cup directly across the board from `cup`, if it faces one.
+    pub fn opposite(&self, cup: CupPos) -> Option<CupPos> {
+        let pits = match *self {
+            Topology::TwoRow { pits, .. } => pits,
+            Topology::FourRow { pits } => pits,
+        };
+        if !self.is_inner(cup) {
+            return None;
+        }
+        // Both players number their row from their own left, so it's mirrored.
+        Some(CupPos {
+            owner: cup.owner.next_player(),
+            pos: pits - 1 - cup.pos,
+        })
+    }
+
+    /// Every pit a seed sown by `player` can land in, in sowing order.
+    pub fn sowing_loop(&self, player: Player) -> Vec<Pit> {
+        (0..self.loop_len())
+            .map(|index| self.loop_pit(player, index))
+            .collect()
+    }
+
+    /// Number of pits in a sowing loop, stores included.
+    pub fn loop_len(&self) -> usize {
+        match *self {
+            Topology::TwoRow { pits, stores } => 2 * (pits + stores as usize),
+            Topology::FourRow { pits } => 2 * pits,
+        }
+    }
+
+    /// Where `pit` comes in the sowing loop it's on.
+    pub fn loop_index(&self, pit: Pit) -> Option<usize> {
+        let per_player = self.cups_per_player();
+        match (*self, pit) {
+            (_, Pit::Cup(cup)) if cup.pos >= per_player => None,
+            (Topology::TwoRow { pits, stores }, pit) => {
+                let (owner, pos) = match pit {
+                    Pit::Cup(cup) => (cup.owner, cup.pos),
+                    Pit::Store(owner) if stores => (owner, pits),
+                    Pit::Store(_) => return None,
+                };
+                Some(match owner {
+                    Player::Player1 => pos,
+                    Player::Player2 => pits + stores as usize + pos,
+                })
+            }
+            (Topology::FourRow { .. }, Pit::Cup(cup)) => Some(cup.pos),
+            (Topology::FourRow { .. }, Pit::Store(_)) => None,
+        }
+    }
+
+    /// The pit at `index` in the sowing loop `player` sows around.
+    pub fn loop_pit(&self, player: Player, index: usize) -> Pit {
+        match *self {
+            Topology::TwoRow { pits, stores } => {
+                let row = pits + stores as usize;
+                let owner = if index < row {
+                    Player::Player1
+                } else {
+                    Player::Player2
+                };
+                match index % row {
+                    pos if pos == pits => Pit::Store(owner),
+                    pos => Pit::Cup(CupPos { owner, pos }),
+                }
+            }
+            Topology::FourRow { .. } => Pit::Cup(CupPos {
+                owner: player,
+                pos: index,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cup(owner: Player, pos: usize) -> CupPos {
+        CupPos { owner, pos }
+    }
+
+    #[test]
+    fn two_row() {
+        let topology = Topology::TwoRow {
+            pits: 2,
+            stores: true,
+        };
+        assert_eq!(
+            vec![
+                Pit::Cup(cup(Player::Player1, 0)),
+                Pit::Cup(cup(Player::Player1, 1)),
+                Pit::Store(Player::Player1),
+                Pit::Cup(cup(Player::Player2, 0)),
+                Pit::Cup(cup(Player::Player2, 1)),
+                Pit::Store(Player::Player2),
+            ],
+            topology.sowing_loop(Player::Player2)
+        );
+        assert_eq!(
+            Some(cup(Player::Player2, 1)),
+            topology.opposite(cup(Player::Player1, 0))
+        );
+        for (index, pit) in topology.sowing_loop(Player::Player1).iter().enumerate() {
+            assert_eq!(Some(index), topology.loop_index(*pit));
+        }
+        assert_eq!(None, topology.loop_index(Pit::Cup(cup(Player::Player1, 2))));
+    }
+
+    #[test]
+    fn four_row() {
+        let topology = Topology::FourRow { pits: 2 };
+        assert_eq!(
+            vec![
+                Pit::Cup(cup(Player::Player2, 0)),
+                Pit::Cup(cup(Player::Player2, 1)),
+                Pit::Cup(cup(Player::Player2, 2)),
+                Pit::Cup(cup(Player::Player2, 3)),
+            ],
+            topology.sowing_loop(Player::Player2)
+        );
+        assert_eq!(
+            Some(cup(Player::Player2, 0)),
+            topology.opposite(cup(Player::Player1, 1))
+        );
+        assert_eq!(None, topology.opposite(cup(Player::Player1, 2)));
+        assert!(!topology.is_inner(cup(Player::Player2, 3)));
+    }
+}
@@ -0,0 +1,40 @@
+//! Zobrist keys: a random-looking 64 bit number for each (pit, seed count)
+//! pair, each bank total and the side to move. A position's key is all of
+//! its keys XORed together, so moving seeds only touches the keys for the
+//! pits they leave and land in.
+//!
+//! Keys are worked out when they're needed rather than kept in a table, so
+//! there's no limit on seed counts. An empty pit has key 0.
+
+use crate::board::MAX_CUPS;
+use crate::Player;
+
+fn key(slot: usize, count: usize) -> u64 {
+    if count == 0 {
+        return 0;
+    }
+    // SplitMix64, seeded with the slot and count.
+    let mut z = ((slot as u64) << 40 ^ count as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The key for the cup at `index` in a board's seeds holding `seeds`.
+pub(crate) fn cup(index: usize, seeds: usize) -> u64 {
+    key(index, seeds)
+}
+
+pub(crate) fn bank(player: Player, seeds: usize) -> u64 {
+    match player {
+        Player::Player1 => key(MAX_CUPS, seeds),
+        Player::Player2 => key(MAX_CUPS + 1, seeds),
+    }
+}
+
+pub(crate) fn to_move(player: Player) -> u64 {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => key(MAX_CUPS + 2, 1),
+    }
+}
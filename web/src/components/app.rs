@@ -1,4 +1,4 @@
-use mancala::{ayoayo::Ayoayo, GameState, MancalaError};
+use mancala::{ayoayo::Ayoayo, game::Game, GameState, MancalaError};
 
 use yew::{html, Callback, ClickEvent, Component, ComponentLink, Html, ShouldRender};
 
@@ -6,15 +6,26 @@ use super::board::Board;
 use super::log;
 
 pub(crate) struct App {
-    game: Ayoayo,
+    game: Game<Ayoayo>,
     onclick: Callback<usize>,
     restart: Callback<ClickEvent>,
+    undo: Callback<ClickEvent>,
+    redo: Callback<ClickEvent>,
     errors: Option<MancalaError>,
 }
 
+// The board animates turns seed by seed, so it needs every seed logged.
+fn new_game() -> Game<Ayoayo> {
+    let mut game = Game::new(Ayoayo::new());
+    game.log_each_seed(true);
+    game
+}
+
 pub(crate) enum Msg {
     Play(usize),
     Restart,
+    Undo,
+    Redo,
 }
 
 impl Component for App {
@@ -23,9 +34,11 @@ impl Component for App {
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
         App {
-            game: Ayoayo::new(),
+            game: new_game(),
             onclick: link.callback(Msg::Play),
             restart: link.callback(|_| Msg::Restart),
+            undo: link.callback(|_| Msg::Undo),
+            redo: link.callback(|_| Msg::Redo),
             errors: None,
         }
     }
@@ -39,11 +52,19 @@ impl Component for App {
                 true
             }
             Msg::Restart => {
-                self.game = Ayoayo::new();
+                self.game = new_game();
                 self.errors = None;
 
                 true
             }
+            Msg::Undo => {
+                self.errors = None;
+                self.game.undo()
+            }
+            Msg::Redo => {
+                self.errors = None;
+                self.game.redo()
+            }
         }
     }
 
@@ -56,11 +77,17 @@ impl Component for App {
             Some(MancalaError::MustFeedError) => info("You need to Sow to your oppoent"),
             Some(MancalaError::NoSeedsToSow) => info("No Seeds in that Cup"),
             Some(MancalaError::NoSuchCup) => info("Cup doesn't exist, how did you click on it?"),
+            Some(MancalaError::GrandSlam) => info("You can't take all of your opponent's seeds"),
+            Some(MancalaError::MustCapture) => info("You have to capture"),
+            Some(MancalaError::IllegalMove) => info("That move isn't allowed"),
+            Some(MancalaError::InvalidBoard) => info("The board is in a state it can't be in"),
+            Some(MancalaError::InvalidNotation) => info("That isn't a position or game record"),
         };
         let game_state = match self.game.state {
             GameState::Won(player) => info(&format!("{} Won!", player)),
             GameState::Draw => info("Nobody Won!"),
             GameState::InProgress(player) => info(&format!("{}'s Turn.", player)),
+            GameState::Simultaneous => info("Both players' Turn."),
         };
 
         html! {
@@ -69,6 +96,8 @@ impl Component for App {
                 {game_state}
                 <Board board=&self.game.clone() play_click=&self.onclick />
                 <button class="restart" onclick=&self.restart>{"restart"}</button>
+                <button class="undo" onclick=&self.undo>{"undo"}</button>
+                <button class="redo" onclick=&self.redo>{"redo"}</button>
             </div>
         }
     }
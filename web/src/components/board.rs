@@ -1,29 +1,39 @@
-use mancala::{ayoayo::Ayoayo, board::Cup, GameState, MancalaError, Player};
+use mancala::{
+    ayoayo::Ayoayo,
+    board::{Cup, MancalaBoard},
+    game::Game,
+    GameState, Player,
+};
 use std::time::Duration;
 use yew::services::{IntervalService, Task};
 use yew::{html, Callback, Component, ComponentLink, Html, Properties, ShouldRender};
 
 use super::log;
 
+const STEP_MILLIS: u64 = 150;
+
 pub(crate) struct Board {
-    board: Ayoayo,
+    board: Game<Ayoayo>,
     link: ComponentLink<Self>,
     play_click: Callback<usize>,
+    // The board being drawn, which trails `board` while a turn is replayed.
+    shown: MancalaBoard,
+    // Boards the last turn passed through that haven't been drawn yet, last first.
+    frames: Vec<MancalaBoard>,
+    interval: IntervalService,
     // Keeping track of the interval
-    job: Box<dyn Task>,
-    // Thoughts: MancalalBoard might be able to "replay" moves...
-    // Does that drastically change what MancalaBoard looks like internally?
-    // Interval replays an iterator a step at a time, so store an iterator here
+    job: Option<Box<dyn Task>>,
 }
 
 #[derive(Properties, Clone)]
 pub(crate) struct Props {
-    pub(crate) board: Ayoayo,
+    pub(crate) board: Game<Ayoayo>,
     pub(crate) play_click: Callback<usize>,
 }
 
 pub(crate) enum Msg {
     Collect(usize),
+    Step,
 }
 
 impl Component for Board {
@@ -33,8 +43,12 @@ impl Component for Board {
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         Board {
             link,
+            shown: props.board.board().clone(),
             board: props.board,
             play_click: props.play_click,
+            frames: Vec::new(),
+            interval: IntervalService::new(),
+            job: None,
         }
     }
 
@@ -44,17 +58,35 @@ impl Component for Board {
                 self.play_click.emit(pos);
                 false
             }
+            Msg::Step => {
+                match self.frames.pop() {
+                    Some(frame) => self.shown = frame,
+                    None => {
+                        self.shown = self.board.board().clone();
+                        self.job = None;
+                    }
+                }
+                true
+            }
         }
     }
 
     fn view(&self) -> Html {
-        let cup_comp = |cup: &Cup| {
+        let legal_moves = self.board.legal_moves();
+        let cups = |player: Player| {
+            self.shown
+                .cups()
+                .into_iter()
+                .filter(move |cup| cup.owner == player)
+        };
+        let cup_comp = |cup: Cup| {
             let pos = cup.pos;
             let seeds = cup.seeds;
             let disabled = match self.board.state {
                 GameState::InProgress(player) => cup.owner != player,
                 _ => true,
-            } || cup.seeds == 0;
+            } || !legal_moves.contains(&pos)
+                || self.job.is_some();
             html! {
                 <button class="cup" disabled=disabled onclick=self.link.callback(move |_| Msg::Collect(pos.clone())) >{seeds}</button>
             }
@@ -63,17 +95,17 @@ impl Component for Board {
         html! {
             <>
                 <div class="bank1name">{"Player 1 Bank"}</div>
-                <div class="bank1">{self.board.get_bank(Player::Player1)}</div>
+                <div class="bank1">{self.shown.get_bank(Player::Player1)}</div>
                 <div class="board">
                     <div>
-                        {self.board.get_cups_for_player(Player::Player1).iter().map(cup_comp).collect::<Html>()}
+                        {cups(Player::Player1).map(cup_comp).collect::<Html>()}
                     </div>
                     <div>
-                        {self.board.get_cups_for_player(Player::Player2).iter().map(cup_comp).collect::<Html>()}
+                        {cups(Player::Player2).map(cup_comp).collect::<Html>()}
                     </div>
                 </div>
                 <div class="bank2name">{"Player 2 Bank"}</div>
-                <div class="bank2">{self.board.get_bank(Player::Player2)}</div>
+                <div class="bank2">{self.shown.get_bank(Player::Player2)}</div>
             </>
         }
     }
@@ -81,15 +113,24 @@ impl Component for Board {
     //     false
     // }
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        log(&format!(
-            "Changes?\n{}\n{}\n{:?}",
-            self.board,
-            props.board,
-            self.board != props.board
-        )[..]);
-        if self.board != props.board {
+        // Compare positions rather than games, which also compare their histories.
+        let changed = self.board.position() != props.board.position()
+            || self.board.state != props.board.state;
+        log(&format!("Changes?\n{}\n{}\n{:?}", self.board, props.board, changed)[..]);
+        if changed {
+            let played = props.board.board().moves().len() > self.board.board().moves().len();
             self.board = props.board;
-            true
+            // Replay a new turn seed by seed; anything else, like an undo, is drawn straight away.
+            self.frames = match self.board.board().replay_last_turn() {
+                Some(turn) if played => turn.collect(),
+                _ => Vec::new(),
+            };
+            self.frames.reverse();
+            self.job = Some(Box::new(self.interval.spawn(
+                Duration::from_millis(STEP_MILLIS),
+                self.link.callback(|_| Msg::Step),
+            )));
+            self.update(Msg::Step)
         } else {
             false
         }